@@ -109,12 +109,12 @@ async fn code_review_prompt(
 
     let messages = vec![PromptMessage {
         role: "user".to_string(),
-        content: PromptContent::Text {
-            text: format!(
+        content: PromptContent::text(
+            format!(
                 "Please review this code:\n\n```\n{}\n```\n\nProvide feedback on:\n- Code quality\n- Best practices\n- Potential bugs",
                 code
             ),
-        },
+        ),
     }];
 
     Ok((Some("Code review prompt".to_string()), messages))
@@ -179,12 +179,12 @@ async fn plan_trip_prompt(
 
     Ok((Some("Trip planning".to_string()), vec![PromptMessage {
         role: "user".to_string(),
-        content: PromptContent::Text {
-            text: format!(
+        content: PromptContent::text(
+            format!(
                 "Plan a {} day trip to {}. Interests: {}. Include activities, restaurants, and tips.",
                 duration, dest, interests
             ),
-        },
+        ),
     }]))
 }
 
@@ -206,12 +206,12 @@ async fn budget_advice_prompt(
 
     Ok((Some("Budget planning".to_string()), vec![PromptMessage {
         role: "user".to_string(),
-        content: PromptContent::Text {
-            text: format!(
+        content: PromptContent::text(
+            format!(
                 "I have ${} for {}. Help me budget for accommodation, food, activities, and transport.",
                 budget, dest
             ),
-        },
+        ),
     }]))
 }
 
@@ -224,6 +224,8 @@ async fn main() -> std::io::Result<()> {
     let server = HttpMcpServer::builder()
         .name("travel-planner-mcp")
         .version("1.0.0")
+        // travel_planner_test.sh sends each request without a session
+        .require_sessions(false)
         // Resources
         .resource(
             "travel://destinations/popular",
@@ -0,0 +1,114 @@
+use crate::server::DEFAULT_MAX_PAYLOAD_SIZE;
+use actix_web::http::KeepAlive;
+use std::time::Duration;
+
+/// HTTP server tuning and request body limits
+///
+/// Options left unset keep actix-web's defaults: one worker per physical CPU,
+/// 5 second keep-alive and client request timeout, 25,000 connections per
+/// worker and a backlog of 1,024.
+///
+/// ```rust
+/// use httpmcp_rust::ServerConfig;
+/// use std::time::Duration;
+///
+/// let config = ServerConfig::new()
+///     .workers(4)
+///     .keep_alive(Duration::from_secs(75))
+///     .client_request_timeout(Duration::from_secs(10))
+///     .max_connections(10_000)
+///     .mcp_payload_limit(16 * 1024 * 1024)
+///     .multipart_payload_limit(100 * 1024 * 1024);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub(crate) workers: Option<usize>,
+    pub(crate) keep_alive: Option<KeepAlive>,
+    pub(crate) client_request_timeout: Option<Duration>,
+    pub(crate) client_disconnect_timeout: Option<Duration>,
+    pub(crate) max_connections: Option<usize>,
+    pub(crate) backlog: Option<u32>,
+    pub(crate) mcp_payload_limit: usize,
+    pub(crate) endpoint_payload_limit: usize,
+    /// `None` leaves multipart uploads unlimited
+    pub(crate) multipart_payload_limit: Option<usize>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            workers: None,
+            keep_alive: None,
+            client_request_timeout: None,
+            client_disconnect_timeout: None,
+            max_connections: None,
+            backlog: None,
+            mcp_payload_limit: DEFAULT_MAX_PAYLOAD_SIZE,
+            endpoint_payload_limit: DEFAULT_MAX_PAYLOAD_SIZE,
+            multipart_payload_limit: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of worker threads
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = Some(workers);
+        self
+    }
+
+    /// How long idle connections are kept open, or `KeepAlive::Disabled`
+    pub fn keep_alive(mut self, keep_alive: impl Into<KeepAlive>) -> Self {
+        self.keep_alive = Some(keep_alive.into());
+        self
+    }
+
+    /// Time a client has to send the request head before getting 408
+    pub fn client_request_timeout(mut self, timeout: Duration) -> Self {
+        self.client_request_timeout = Some(timeout);
+        self
+    }
+
+    /// Time a client has to acknowledge the connection shutting down
+    pub fn client_disconnect_timeout(mut self, timeout: Duration) -> Self {
+        self.client_disconnect_timeout = Some(timeout);
+        self
+    }
+
+    /// Maximum concurrent connections per worker
+    pub fn max_connections(mut self, connections: usize) -> Self {
+        self.max_connections = Some(connections);
+        self
+    }
+
+    /// Maximum number of pending connections
+    ///
+    /// Only applies to sockets the server binds itself.
+    pub fn backlog(mut self, backlog: u32) -> Self {
+        self.backlog = Some(backlog);
+        self
+    }
+
+    /// Maximum size in bytes of a JSON-RPC message on `/mcp`, `/messages` and
+    /// WebSocket frames (default 2 MiB)
+    pub fn mcp_payload_limit(mut self, bytes: usize) -> Self {
+        self.mcp_payload_limit = bytes;
+        self
+    }
+
+    /// Maximum size in bytes of a custom endpoint's JSON body (default 2 MiB)
+    pub fn endpoint_payload_limit(mut self, bytes: usize) -> Self {
+        self.endpoint_payload_limit = bytes;
+        self
+    }
+
+    /// Maximum size in bytes of a multipart upload (default unlimited)
+    pub fn multipart_payload_limit(mut self, bytes: usize) -> Self {
+        self.multipart_payload_limit = Some(bytes);
+        self
+    }
+}
@@ -1,9 +1,18 @@
+use crate::jsonrpc::{JsonRpcMessage, JsonRpcRequest};
+use crate::protocol::{LogLevel, LoggingMessageParams, ProgressParams};
 use actix_web::http::header::HeaderMap;
+use serde_json::Value;
+use std::collections::HashMap;
 use std::net::SocketAddr;
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
 /// Request context passed to all handler methods
+///
+/// New fields may be added in minor releases, so create one with
+/// [`RequestContext::new`] and set fields afterwards.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct RequestContext {
     /// HTTP headers from the request
     pub headers: HeaderMap,
@@ -17,23 +26,90 @@ pub struct RequestContext {
     /// Request path
     pub path: String,
 
-    /// Remote client address
+    /// Remote client address; `None` for Unix domain socket connections
     pub remote_addr: Option<SocketAddr>,
+
+    /// MCP session ID from the `Mcp-Session-Id` header, if any
+    pub session_id: Option<String>,
+
+    /// Channel for notifications related to this request, when the response is streamed
+    pub notifier: Option<RequestNotifier>,
+
+    /// Client certificate verified during the TLS handshake, if any
+    pub peer_certificate: Option<PeerCertificate>,
+
+    /// Attributes of the request's session, empty without one
+    pub session_attributes: HashMap<String, Value>,
+}
+
+/// Client certificate presented on a TLS connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerCertificate {
+    /// Subject distinguished name, such as `CN=agent-1, O=Example`
+    pub subject: String,
+
+    /// Subject common name, if present
+    pub common_name: Option<String>,
+
+    /// The DER-encoded certificate
+    pub der: Vec<u8>,
+}
+
+/// Sends notifications to the client on the stream answering a request
+#[derive(Debug, Clone)]
+pub struct RequestNotifier {
+    tx: mpsc::UnboundedSender<JsonRpcMessage>,
+    progress_token: Option<Value>,
+}
+
+impl RequestNotifier {
+    pub fn new(tx: mpsc::UnboundedSender<JsonRpcMessage>, progress_token: Option<Value>) -> Self {
+        Self { tx, progress_token }
+    }
+
+    /// Create a notifier for a request, picking up its `_meta.progressToken`
+    pub fn for_request(tx: mpsc::UnboundedSender<JsonRpcMessage>, req: &JsonRpcRequest) -> Self {
+        let progress_token = req
+            .params
+            .as_ref()
+            .and_then(|p| p.get("_meta"))
+            .and_then(|m| m.get("progressToken"))
+            .cloned();
+        Self::new(tx, progress_token)
+    }
+
+    /// Send a notification; returns false if the stream has closed
+    pub fn send(&self, method: impl Into<String>, params: Value) -> bool {
+        let notification = JsonRpcRequest::new(method, Some(params), None);
+        self.tx.send(notification.into()).is_ok()
+    }
 }
 
 impl RequestContext {
+    /// Context for a request, taking the session ID from the `Mcp-Session-Id` header
+    ///
+    /// The request ID is a fresh UUID; the remaining fields start empty.
     pub fn new(
         headers: HeaderMap,
         method: String,
         path: String,
         remote_addr: Option<SocketAddr>,
     ) -> Self {
+        let session_id = headers
+            .get(crate::session::SESSION_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+
         Self {
             headers,
             request_id: Uuid::new_v4().to_string(),
             method,
             path,
             remote_addr,
+            session_id,
+            notifier: None,
+            peer_certificate: None,
+            session_attributes: HashMap::new(),
         }
     }
 
@@ -45,6 +121,11 @@ impl RequestContext {
             .map(|s| s.to_string())
     }
 
+    /// Get one of the session's attributes
+    pub fn session_attribute(&self, key: &str) -> Option<&Value> {
+        self.session_attributes.get(key)
+    }
+
     /// Get authorization header
     pub fn get_authorization(&self) -> Option<String> {
         self.get_header("authorization")
@@ -56,6 +137,55 @@ impl RequestContext {
             .and_then(|auth| auth.strip_prefix("Bearer ").map(|s| s.to_string()))
     }
 
+    /// Get the negotiated protocol version header, if sent
+    pub fn get_protocol_version(&self) -> Option<String> {
+        self.get_header(crate::version::PROTOCOL_VERSION_HEADER)
+    }
+
+    /// Report progress on this request
+    ///
+    /// Only sent when the response is streamed and the client supplied a
+    /// progress token. Returns whether the notification was sent.
+    pub fn notify_progress(
+        &self,
+        progress: f64,
+        total: Option<f64>,
+        message: Option<&str>,
+    ) -> bool {
+        let Some(notifier) = &self.notifier else {
+            return false;
+        };
+        let Some(progress_token) = notifier.progress_token.clone() else {
+            return false;
+        };
+        let params = ProgressParams {
+            progress_token,
+            progress,
+            total,
+            message: message.map(|m| m.to_string()),
+        };
+        serde_json::to_value(params)
+            .map(|params| notifier.send("notifications/progress", params))
+            .unwrap_or(false)
+    }
+
+    /// Send a log message to the client alongside this request's response
+    ///
+    /// Returns whether the notification was sent.
+    pub fn log(&self, level: LogLevel, data: impl Into<Value>) -> bool {
+        let Some(notifier) = &self.notifier else {
+            return false;
+        };
+        let params = LoggingMessageParams {
+            level,
+            logger: None,
+            data: data.into(),
+        };
+        serde_json::to_value(params)
+            .map(|params| notifier.send("notifications/message", params))
+            .unwrap_or(false)
+    }
+
     /// Get custom header by name
     pub fn get_custom_header(&self, name: &str) -> Option<String> {
         self.get_header(name)
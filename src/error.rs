@@ -1,4 +1,4 @@
-use crate::jsonrpc::{error_codes, JsonRpcError};
+use crate::jsonrpc::{error_codes, JsonRpcError, RequestId};
 use actix_web::{http::StatusCode, HttpResponse, ResponseError};
 use thiserror::Error;
 
@@ -28,6 +28,30 @@ pub enum McpError {
     #[error("Prompt not found: {0}")]
     PromptNotFound(String),
 
+    #[error("Server not initialized")]
+    NotInitialized,
+
+    #[error("Session not found: {0}")]
+    SessionNotFound(String),
+
+    #[error("Request body exceeds {0} bytes")]
+    PayloadTooLarge(usize),
+
+    #[error("Unsupported media type: {0}")]
+    UnsupportedMediaType(String),
+
+    #[error("Not acceptable: {0}")]
+    NotAcceptable(String),
+
+    #[error("Origin not allowed: {0}")]
+    OriginNotAllowed(String),
+
+    #[error("Host not allowed: {0}")]
+    HostNotAllowed(String),
+
+    #[error("Server is shutting down")]
+    ShuttingDown,
+
     #[error("Authentication required")]
     AuthenticationRequired,
 
@@ -42,6 +66,14 @@ pub enum McpError {
 
     #[error("JSON-RPC error: {0}")]
     JsonRpcError(String),
+
+    /// Error response from a client to [`crate::HttpMcpServer::send_request`]
+    #[error("Client error {}: {}", .0.code, .0.message)]
+    ClientError(JsonRpcError),
+
+    /// A client did not answer [`crate::HttpMcpServer::send_request`] in time
+    #[error("Client did not respond to {0} in time")]
+    ClientTimeout(String),
 }
 
 impl From<JsonRpcError> for McpError {
@@ -93,6 +125,46 @@ impl McpError {
                 message: format!("Prompt not found: {}", name),
                 data: Some(serde_json::json!({ "prompt": name })),
             },
+            McpError::NotInitialized => JsonRpcError {
+                code: error_codes::INVALID_REQUEST,
+                message: "Server not initialized".to_string(),
+                data: None,
+            },
+            McpError::SessionNotFound(id) => JsonRpcError {
+                code: error_codes::INVALID_REQUEST,
+                message: format!("Session not found: {}", id),
+                data: None,
+            },
+            McpError::PayloadTooLarge(limit) => JsonRpcError {
+                code: error_codes::INVALID_REQUEST,
+                message: format!("Request body exceeds {} bytes", limit),
+                data: Some(serde_json::json!({ "limit": limit })),
+            },
+            McpError::UnsupportedMediaType(msg) => JsonRpcError {
+                code: error_codes::INVALID_REQUEST,
+                message: format!("Unsupported media type: {}", msg),
+                data: None,
+            },
+            McpError::NotAcceptable(msg) => JsonRpcError {
+                code: error_codes::INVALID_REQUEST,
+                message: format!("Not acceptable: {}", msg),
+                data: None,
+            },
+            McpError::OriginNotAllowed(origin) => JsonRpcError {
+                code: error_codes::INVALID_REQUEST,
+                message: format!("Origin not allowed: {}", origin),
+                data: None,
+            },
+            McpError::HostNotAllowed(host) => JsonRpcError {
+                code: error_codes::INVALID_REQUEST,
+                message: format!("Host not allowed: {}", host),
+                data: None,
+            },
+            McpError::ShuttingDown => JsonRpcError {
+                code: error_codes::INTERNAL_ERROR,
+                message: "Server is shutting down".to_string(),
+                data: None,
+            },
             McpError::AuthenticationRequired => JsonRpcError {
                 code: error_codes::INVALID_REQUEST,
                 message: "Authentication required".to_string(),
@@ -118,6 +190,12 @@ impl McpError {
                 message: msg.clone(),
                 data: None,
             },
+            McpError::ClientError(error) => error.clone(),
+            McpError::ClientTimeout(method) => JsonRpcError {
+                code: error_codes::INTERNAL_ERROR,
+                message: format!("Client did not respond to {} in time", method),
+                data: None,
+            },
         }
     }
 }
@@ -132,6 +210,14 @@ impl ResponseError for McpError {
             McpError::ResourceNotFound(_) => StatusCode::NOT_FOUND,
             McpError::ToolNotFound(_) => StatusCode::NOT_FOUND,
             McpError::PromptNotFound(_) => StatusCode::NOT_FOUND,
+            McpError::NotInitialized => StatusCode::BAD_REQUEST,
+            McpError::SessionNotFound(_) => StatusCode::NOT_FOUND,
+            McpError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            McpError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            McpError::NotAcceptable(_) => StatusCode::NOT_ACCEPTABLE,
+            McpError::OriginNotAllowed(_) => StatusCode::FORBIDDEN,
+            McpError::HostNotAllowed(_) => StatusCode::FORBIDDEN,
+            McpError::ShuttingDown => StatusCode::SERVICE_UNAVAILABLE,
             McpError::AuthenticationRequired => StatusCode::UNAUTHORIZED,
             McpError::AuthorizationFailed(_) => StatusCode::FORBIDDEN,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
@@ -139,13 +225,53 @@ impl ResponseError for McpError {
     }
 
     fn error_response(&self) -> HttpResponse {
-        let jsonrpc_error = self.to_jsonrpc_error();
+        self.to_http_response(None)
+    }
+}
+
+impl McpError {
+    /// HTTP response with this error's status and a JSON-RPC error body
+    ///
+    /// Used for transport-level failures (authentication, unknown session,
+    /// bad headers); `id` is the ID of the request that failed, if known.
+    pub fn to_http_response(&self, id: Option<RequestId>) -> HttpResponse {
         HttpResponse::build(self.status_code()).json(serde_json::json!({
             "jsonrpc": "2.0",
-            "error": jsonrpc_error,
-            "id": null
+            "error": self.to_jsonrpc_error(),
+            "id": id
         }))
     }
 }
 
+/// HTTP status used for responses carrying a JSON-RPC error
+///
+/// Transport problems such as failed authentication or an unknown session
+/// always use their own status code; this policy only covers errors returned
+/// by request handlers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorStatusPolicy {
+    /// Always answer with 200 OK and report the error in the JSON-RPC body
+    #[default]
+    Ok,
+    /// Map the JSON-RPC error code to 400, 404 or 500
+    FromErrorCode,
+}
+
+impl ErrorStatusPolicy {
+    pub fn status_for(&self, error: &JsonRpcError) -> StatusCode {
+        match self {
+            ErrorStatusPolicy::Ok => StatusCode::OK,
+            ErrorStatusPolicy::FromErrorCode => match error.code {
+                error_codes::PARSE_ERROR
+                | error_codes::INVALID_REQUEST
+                | error_codes::INVALID_PARAMS => StatusCode::BAD_REQUEST,
+                error_codes::METHOD_NOT_FOUND | error_codes::RESOURCE_NOT_FOUND => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, McpError>;
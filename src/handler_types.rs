@@ -1,6 +1,7 @@
 use crate::context::RequestContext;
 use crate::error::Result;
 use crate::protocol::*;
+use crate::session::{Session, SessionCloseReason};
 use actix_multipart::Multipart;
 use actix_web::HttpResponse;
 use futures::future::BoxFuture;
@@ -10,11 +11,88 @@ use std::sync::Arc;
 
 /// Tool handler function signature
 pub type ToolHandler = Box<
-    dyn Fn(HashMap<String, Value>, RequestContext) -> BoxFuture<'static, Result<Value>>
+    dyn Fn(HashMap<String, Value>, RequestContext) -> BoxFuture<'static, Result<ToolsCallResult>>
         + Send
         + Sync,
 >;
 
+/// Conversion from a tool handler's return value into a `tools/call` result
+///
+/// Implemented for common return types; implement it for your own types to
+/// control exactly which content blocks a tool produces.
+pub trait IntoToolResult {
+    fn into_tool_result(self) -> Result<ToolsCallResult>;
+}
+
+impl IntoToolResult for ToolsCallResult {
+    fn into_tool_result(self) -> Result<ToolsCallResult> {
+        Ok(self)
+    }
+}
+
+impl IntoToolResult for Vec<ToolContent> {
+    fn into_tool_result(self) -> Result<ToolsCallResult> {
+        Ok(ToolsCallResult {
+            content: self,
+            is_error: None,
+        })
+    }
+}
+
+impl IntoToolResult for ToolContent {
+    fn into_tool_result(self) -> Result<ToolsCallResult> {
+        vec![self].into_tool_result()
+    }
+}
+
+/// `(content, is_error)`, as returned by [`crate::handlers::ToolProvider::call_tool`]
+impl IntoToolResult for (Vec<ToolContent>, bool) {
+    fn into_tool_result(self) -> Result<ToolsCallResult> {
+        let (content, is_error) = self;
+        Ok(ToolsCallResult {
+            content,
+            is_error: Some(is_error),
+        })
+    }
+}
+
+/// JSON values are returned as a single text block
+impl IntoToolResult for Value {
+    fn into_tool_result(self) -> Result<ToolsCallResult> {
+        ToolContent::text(self.to_string()).into_tool_result()
+    }
+}
+
+impl IntoToolResult for String {
+    fn into_tool_result(self) -> Result<ToolsCallResult> {
+        ToolContent::text(self).into_tool_result()
+    }
+}
+
+impl IntoToolResult for &'static str {
+    fn into_tool_result(self) -> Result<ToolsCallResult> {
+        ToolContent::text(self).into_tool_result()
+    }
+}
+
+/// Tool policy function signature
+///
+/// Called before every `tools/call` with the tool's metadata. Returning an error
+/// refuses the call without invoking the handler.
+pub type ToolPolicy = Arc<dyn Fn(&Tool, &RequestContext) -> Result<()> + Send + Sync>;
+
+/// Hook computing a session's attributes from its `initialize` request
+pub type SessionAttributesHook =
+    Arc<dyn Fn(&InitializeParams, &RequestContext) -> Result<HashMap<String, Value>> + Send + Sync>;
+
+/// Hook called when a client sends `notifications/initialized`
+pub type InitializedHook =
+    Arc<dyn Fn(Implementation, RequestContext) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// Hook called after a session is closed, for any reason
+pub type SessionClosedHook =
+    Arc<dyn Fn(Session, SessionCloseReason) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
 /// Resource list handler function signature
 pub type ResourceListHandler = Box<
     dyn Fn(
@@ -1,32 +1,51 @@
+use crate::context::RequestContext;
 use crate::error::{McpError, Result};
 use crate::jsonrpc::{JsonRpcRequest, JsonRpcResponse};
-use crate::protocol::{Implementation, InitializeParams, InitializeResult, ServerCapabilities};
+use crate::protocol::{InitializeParams, InitializeResult};
+use crate::server::HttpMcpServer;
+use crate::version::ProtocolVersion;
 use serde_json::Value;
 
 /// Handle initialize request
+///
+/// Negotiates the protocol version and, when the request carries a session ID,
+/// records the session with its negotiated version and attributes.
 pub fn handle_initialize(
     req: &JsonRpcRequest,
-    server_info: Implementation,
-    capabilities: ServerCapabilities,
+    ctx: &RequestContext,
+    server: &HttpMcpServer,
 ) -> Result<JsonRpcResponse> {
     let params: InitializeParams =
         serde_json::from_value(req.params.clone().unwrap_or(Value::Null))
             .map_err(|e| McpError::InvalidParams(format!("Invalid initialize params: {}", e)))?;
 
-    // Validate protocol version
-    if !params.protocol_version.starts_with("2024-")
-        && !params.protocol_version.starts_with("2025-")
-    {
-        return Err(McpError::InvalidRequest(format!(
-            "Unsupported protocol version: {}",
-            params.protocol_version
-        )));
+    let version = ProtocolVersion::negotiate(&params.protocol_version);
+    if version.as_str() != params.protocol_version {
+        tracing::debug!(
+            "Client requested unsupported protocol version {}, offering {}",
+            params.protocol_version,
+            version
+        );
+    }
+
+    let attributes = match &server.session_attributes {
+        Some(hook) => hook(&params, ctx)?,
+        None => Default::default(),
+    };
+
+    if let Some(session_id) = &ctx.session_id {
+        server
+            .sessions
+            .initialize(session_id, version, Some(params.client_info));
+        for (key, value) in attributes {
+            server.sessions.set_attribute(session_id, key, value);
+        }
     }
 
     let result = InitializeResult {
-        protocol_version: params.protocol_version,
-        capabilities,
-        server_info,
+        protocol_version: version.to_string(),
+        capabilities: server.capabilities.clone(),
+        server_info: server.server_info.clone(),
     };
 
     Ok(JsonRpcResponse::success(
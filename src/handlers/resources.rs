@@ -42,10 +42,20 @@ pub trait ResourceProvider: Send + Sync {
         Ok(vec![])
     }
 
+    /// Whether this provider accepts subscriptions (default: false)
+    ///
+    /// The server advertises the `subscribe` capability and forwards
+    /// `resources/subscribe` and `resources/unsubscribe` only when a provider
+    /// returns true.
+    fn supports_subscribe(&self) -> bool {
+        false
+    }
+
     /// Subscribe to resource changes (optional)
     ///
     /// Called when a client subscribes to changes for a specific resource.
-    /// Return Ok if subscription is accepted.
+    /// Return Ok if subscription is accepted, or `McpError::ResourceNotFound`
+    /// for URIs this provider does not own.
     async fn subscribe(&self, _uri: &str, _ctx: &RequestContext) -> Result<()> {
         Ok(())
     }
@@ -20,10 +20,32 @@ pub struct JsonRpcResponse {
     pub result: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<JsonRpcError>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// `null` when the request ID could not be determined
     pub id: Option<RequestId>,
 }
 
+/// Any JSON-RPC 2.0 message sent to a client: a request, notification or response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum JsonRpcMessage {
+    Request(JsonRpcRequest),
+    Response(JsonRpcResponse),
+    /// Responses to a batch, for protocol revisions before 2025-06-18
+    Batch(Vec<JsonRpcResponse>),
+}
+
+impl From<JsonRpcRequest> for JsonRpcMessage {
+    fn from(request: JsonRpcRequest) -> Self {
+        JsonRpcMessage::Request(request)
+    }
+}
+
+impl From<JsonRpcResponse> for JsonRpcMessage {
+    fn from(response: JsonRpcResponse) -> Self {
+        JsonRpcMessage::Response(response)
+    }
+}
+
 /// JSON-RPC 2.0 Error
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcError {
@@ -67,6 +89,28 @@ impl JsonRpcRequest {
         self.id.is_none()
     }
 
+    /// Parse and validate a request body
+    ///
+    /// On failure the error to send back is returned with the request ID, if one
+    /// could be read: a parse error for malformed JSON, or an invalid request
+    /// error for JSON that is not a valid request.
+    pub fn parse(body: &[u8]) -> Result<Self, (JsonRpcError, Option<RequestId>)> {
+        let value: Value =
+            serde_json::from_slice(body).map_err(|_| (JsonRpcError::parse_error(), None))?;
+        let id: Option<RequestId> = value
+            .get("id")
+            .and_then(|id| serde_json::from_value(id.clone()).ok());
+
+        let request: Self = serde_json::from_value(value).map_err(|e| {
+            (
+                JsonRpcError::invalid_request(format!("Invalid request: {}", e)),
+                id.clone(),
+            )
+        })?;
+        request.validate().map_err(|e| (e, id))?;
+        Ok(request)
+    }
+
     pub fn validate(&self) -> Result<(), JsonRpcError> {
         if self.jsonrpc != "2.0" {
             return Err(JsonRpcError {
@@ -176,4 +220,18 @@ mod tests {
         let req = JsonRpcRequest::new("test", None, Some(RequestId::Number(1)));
         assert!(!req.is_notification());
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let (err, id) = JsonRpcRequest::parse(b"{not json").unwrap_err();
+        assert_eq!(err.code, error_codes::PARSE_ERROR);
+        assert!(id.is_none());
+
+        let (err, id) = JsonRpcRequest::parse(br#"{"jsonrpc": "2.0", "id": 3}"#).unwrap_err();
+        assert_eq!(err.code, error_codes::INVALID_REQUEST);
+        assert!(matches!(id, Some(RequestId::Number(3))));
+
+        let req = JsonRpcRequest::parse(br#"{"jsonrpc": "2.0", "id": "a", "method": "ping"}"#);
+        assert_eq!(req.unwrap().method, "ping");
+    }
 }
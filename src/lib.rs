@@ -71,6 +71,7 @@
 //! ```
 
 pub mod auth;
+pub mod config;
 pub mod context;
 pub mod error;
 pub mod handler_types;
@@ -80,20 +81,36 @@ pub mod metadata;
 pub mod middleware;
 pub mod protocol;
 pub mod server;
+pub mod session;
+pub mod shutdown;
 pub mod sse;
+pub mod stdio;
+#[cfg(feature = "tls")]
+pub mod tls;
+#[cfg(feature = "tower")]
+pub mod tower;
 pub mod transport;
+pub mod version;
 
 // Re-export commonly used types
-pub use context::RequestContext;
-pub use error::{McpError, Result};
-pub use metadata::{EndpointMeta, PromptMeta, ResourceMeta, ToolMeta};
+pub use config::ServerConfig;
+pub use context::{PeerCertificate, RequestContext, RequestNotifier};
+pub use error::{ErrorStatusPolicy, McpError, Result};
+pub use handler_types::IntoToolResult;
+pub use metadata::{EndpointMeta, PromptMeta, ResourceMeta, Schema, ToolMeta};
+pub use middleware::CorsConfig;
 pub use server::{HttpMcpServer, HttpMcpServerBuilder};
+pub use session::{Session, SessionCloseReason, SessionState};
+pub use shutdown::ServerHandle;
+#[cfg(feature = "tls")]
+pub use tls::TlsConfig;
+pub use version::{ProtocolVersion, SUPPORTED_PROTOCOL_VERSIONS};
 
 // Re-export protocol types
 pub use protocol::{
-    Implementation, Prompt, PromptArgument, PromptContent, PromptMessage, PromptsGetParams,
-    PromptsGetResult, PromptsListParams, PromptsListResult, Resource, ResourceContents,
-    ResourceTemplate, ResourcesListParams, ResourcesListResult, ResourcesReadParams,
-    ResourcesReadResult, ServerCapabilities, Tool, ToolContent, ToolsCallParams, ToolsCallResult,
-    ToolsListResult,
+    Annotations, ContentBlock, Implementation, Prompt, PromptArgument, PromptContent,
+    PromptMessage, PromptsGetParams, PromptsGetResult, PromptsListParams, PromptsListResult,
+    Resource, ResourceContents, ResourceTemplate, ResourcesListParams, ResourcesListResult,
+    ResourcesReadParams, ResourcesReadResult, ServerCapabilities, Tool, ToolAnnotations,
+    ToolContent, ToolsCallParams, ToolsCallResult, ToolsListResult,
 };
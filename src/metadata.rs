@@ -1,27 +1,271 @@
+use crate::error::{McpError, Result};
 use crate::protocol::*;
-use serde_json::json;
+use serde_json::{json, Map, Value};
+
+/// JSON Schema builder for tool parameters
+///
+/// Produces draft 2020-12 schemas. Use the typed constructors for common cases,
+/// or [`Schema::raw`] to pass a hand-written schema through unchanged.
+///
+/// ```
+/// use httpmcp_rust::metadata::Schema;
+///
+/// let units = Schema::string()
+///     .enum_values(["metric", "imperial"])
+///     .default_value("metric");
+/// let days = Schema::integer().minimum(1).maximum(16);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    value: Map<String, Value>,
+}
+
+impl Schema {
+    /// Empty schema that accepts any value
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    /// Schema with the given `type` keyword
+    pub fn of_type(schema_type: impl Into<String>) -> Self {
+        Self::any().set("type", json!(schema_type.into()))
+    }
+
+    pub fn string() -> Self {
+        Self::of_type("string")
+    }
+
+    pub fn number() -> Self {
+        Self::of_type("number")
+    }
+
+    pub fn integer() -> Self {
+        Self::of_type("integer")
+    }
+
+    pub fn boolean() -> Self {
+        Self::of_type("boolean")
+    }
+
+    pub fn null() -> Self {
+        Self::of_type("null")
+    }
+
+    /// Array schema whose elements match `items`
+    pub fn array(items: Schema) -> Self {
+        Self::of_type("array").set("items", items.into_value())
+    }
+
+    /// Object schema; add fields with [`Schema::property`]
+    pub fn object() -> Self {
+        Self::of_type("object").set("properties", json!({}))
+    }
+
+    /// Reference to a definition registered with [`ToolMeta::def`]
+    pub fn reference(name: impl AsRef<str>) -> Self {
+        Self::any().set("$ref", json!(format!("#/$defs/{}", name.as_ref())))
+    }
+
+    /// Value must match exactly one of the given schemas
+    pub fn one_of(variants: impl IntoIterator<Item = Schema>) -> Self {
+        Self::any().set("oneOf", Self::list(variants))
+    }
+
+    /// Value must match at least one of the given schemas
+    pub fn any_of(variants: impl IntoIterator<Item = Schema>) -> Self {
+        Self::any().set("anyOf", Self::list(variants))
+    }
+
+    /// Use a raw JSON Schema as-is
+    ///
+    /// Non-object values (e.g. `true`) are wrapped in `allOf` so they remain valid.
+    pub fn raw(schema: Value) -> Self {
+        match schema {
+            Value::Object(value) => Self { value },
+            other => Self::any().set("allOf", json!([other])),
+        }
+    }
+
+    pub fn title(self, title: impl Into<String>) -> Self {
+        self.set("title", json!(title.into()))
+    }
+
+    pub fn description(self, desc: impl Into<String>) -> Self {
+        self.set("description", json!(desc.into()))
+    }
+
+    /// Restrict the value to a fixed set of choices
+    pub fn enum_values<I, V>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        let values: Vec<Value> = values.into_iter().map(Into::into).collect();
+        self.set("enum", Value::Array(values))
+    }
+
+    pub fn const_value(self, value: impl Into<Value>) -> Self {
+        self.set("const", value.into())
+    }
+
+    pub fn default_value(self, value: impl Into<Value>) -> Self {
+        self.set("default", value.into())
+    }
+
+    pub fn examples<I, V>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        let values: Vec<Value> = values.into_iter().map(Into::into).collect();
+        self.set("examples", Value::Array(values))
+    }
+
+    pub fn minimum(self, min: impl Into<Value>) -> Self {
+        self.set("minimum", min.into())
+    }
+
+    pub fn maximum(self, max: impl Into<Value>) -> Self {
+        self.set("maximum", max.into())
+    }
+
+    pub fn exclusive_minimum(self, min: impl Into<Value>) -> Self {
+        self.set("exclusiveMinimum", min.into())
+    }
+
+    pub fn exclusive_maximum(self, max: impl Into<Value>) -> Self {
+        self.set("exclusiveMaximum", max.into())
+    }
+
+    pub fn multiple_of(self, factor: impl Into<Value>) -> Self {
+        self.set("multipleOf", factor.into())
+    }
+
+    pub fn min_length(self, len: u64) -> Self {
+        self.set("minLength", json!(len))
+    }
+
+    pub fn max_length(self, len: u64) -> Self {
+        self.set("maxLength", json!(len))
+    }
+
+    /// ECMA-262 regular expression the string must match
+    pub fn pattern(self, pattern: impl Into<String>) -> Self {
+        self.set("pattern", json!(pattern.into()))
+    }
+
+    /// String format such as `date-time`, `email` or `uri`
+    pub fn format(self, format: impl Into<String>) -> Self {
+        self.set("format", json!(format.into()))
+    }
+
+    pub fn min_items(self, count: u64) -> Self {
+        self.set("minItems", json!(count))
+    }
+
+    pub fn max_items(self, count: u64) -> Self {
+        self.set("maxItems", json!(count))
+    }
+
+    pub fn unique_items(self, unique: bool) -> Self {
+        self.set("uniqueItems", json!(unique))
+    }
+
+    /// Add a property to an object schema
+    ///
+    /// A schema without a `type` becomes an object schema.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the schema has a `type` other than `"object"`, such as
+    /// `Schema::string().property(..)`.
+    pub fn property(mut self, name: impl Into<String>, schema: Schema) -> Self {
+        let name = name.into();
+        match self.value.get("type") {
+            None => self = self.set("type", json!("object")),
+            Some(t) if t == "object" => {}
+            Some(t) => panic!("cannot add property '{}' to a schema of type {}", name, t),
+        }
+        let properties = self.value.entry("properties").or_insert_with(|| json!({}));
+        if let Value::Object(map) = properties {
+            map.insert(name, schema.into_value());
+        }
+        self
+    }
+
+    /// Mark object properties as required
+    pub fn required(self, fields: &[&str]) -> Self {
+        self.set("required", json!(fields))
+    }
+
+    /// Allow or forbid properties not listed in `properties`
+    pub fn additional_properties(self, allowed: bool) -> Self {
+        self.set("additionalProperties", json!(allowed))
+    }
+
+    /// Set an arbitrary keyword not covered by the builder
+    pub fn keyword(self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.set(key, value.into())
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::Object(self.value.clone())
+    }
+
+    pub fn into_value(self) -> Value {
+        Value::Object(self.value)
+    }
+
+    fn set(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.value.insert(key.into(), value);
+        self
+    }
+
+    fn list(schemas: impl IntoIterator<Item = Schema>) -> Value {
+        Value::Array(schemas.into_iter().map(Schema::into_value).collect())
+    }
+}
+
+impl From<Value> for Schema {
+    fn from(schema: Value) -> Self {
+        Self::raw(schema)
+    }
+}
+
+impl From<Schema> for Value {
+    fn from(schema: Schema) -> Self {
+        schema.into_value()
+    }
+}
 
 /// Metadata builder for tools
 #[derive(Debug, Clone)]
 pub struct ToolMeta {
+    title: Option<String>,
     description: Option<String>,
+    annotations: ToolAnnotations,
     params: Vec<ParamMeta>,
     required: Vec<String>,
+    defs: Vec<(String, Schema)>,
+    input_schema: Option<Value>,
 }
 
 #[derive(Debug, Clone)]
 struct ParamMeta {
     name: String,
-    param_type: String,
-    description: String,
+    schema: Schema,
 }
 
 impl ToolMeta {
     pub fn new() -> Self {
         Self {
+            title: None,
             description: None,
+            annotations: ToolAnnotations::default(),
             params: Vec::new(),
             required: Vec::new(),
+            defs: Vec::new(),
+            input_schema: None,
         }
     }
 
@@ -30,17 +274,56 @@ impl ToolMeta {
         self
     }
 
+    /// Human-readable display name
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Tool does not modify its environment (`readOnlyHint`)
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.annotations.read_only_hint = Some(read_only);
+        self
+    }
+
+    /// Tool may perform destructive updates (`destructiveHint`)
+    pub fn destructive(mut self, destructive: bool) -> Self {
+        self.annotations.destructive_hint = Some(destructive);
+        self
+    }
+
+    /// Repeated calls with the same arguments have no additional effect (`idempotentHint`)
+    pub fn idempotent(mut self, idempotent: bool) -> Self {
+        self.annotations.idempotent_hint = Some(idempotent);
+        self
+    }
+
+    /// Tool interacts with external entities (`openWorldHint`)
+    pub fn open_world(mut self, open_world: bool) -> Self {
+        self.annotations.open_world_hint = Some(open_world);
+        self
+    }
+
+    /// Replace all annotations at once
+    pub fn annotations(mut self, annotations: ToolAnnotations) -> Self {
+        self.annotations = annotations;
+        self
+    }
+
     pub fn param(
-        mut self,
+        self,
         name: impl Into<String>,
         param_type: impl Into<String>,
         description: impl Into<String>,
     ) -> Self {
-        self.params.push(ParamMeta {
-            name: name.into(),
-            param_type: param_type.into(),
-            description: description.into(),
-        });
+        self.param_schema(name, Schema::of_type(param_type).description(description))
+    }
+
+    /// Add a parameter described by a full [`Schema`]
+    pub fn param_schema(mut self, name: impl Into<String>, schema: Schema) -> Self {
+        let name = name.into();
+        self.params.retain(|param| param.name != name);
+        self.params.push(ParamMeta { name, schema });
         self
     }
 
@@ -49,17 +332,53 @@ impl ToolMeta {
         self
     }
 
+    /// Register a reusable definition under `$defs`, referenced via [`Schema::reference`]
+    pub fn def(mut self, name: impl Into<String>, schema: Schema) -> Self {
+        let name = name.into();
+        self.defs.retain(|(existing, _)| *existing != name);
+        self.defs.push((name, schema));
+        self
+    }
+
+    /// Use a full schema for the whole input, ignoring params, required and defs
+    ///
+    /// MCP requires tool input schemas to describe an object, so this fails
+    /// unless the schema's `type` is `"object"`.
+    pub fn input_schema(mut self, schema: impl Into<Schema>) -> Result<Self> {
+        let schema = schema.into().into_value();
+        if schema.get("type") != Some(&json!("object")) {
+            return Err(McpError::InvalidParams(
+                "Tool input schema must have type \"object\"".to_string(),
+            ));
+        }
+        self.input_schema = Some(schema);
+        Ok(self)
+    }
+
     pub fn to_tool(&self, name: impl Into<String>) -> Tool {
-        let mut properties = serde_json::Map::new();
+        let annotations = if self.annotations == ToolAnnotations::default() {
+            None
+        } else {
+            Some(self.annotations.clone())
+        };
 
+        Tool {
+            name: name.into(),
+            title: self.title.clone(),
+            description: self.description.clone(),
+            input_schema: self.build_input_schema(),
+            annotations,
+        }
+    }
+
+    fn build_input_schema(&self) -> Value {
+        if let Some(schema) = &self.input_schema {
+            return schema.clone();
+        }
+
+        let mut properties = Map::new();
         for param in &self.params {
-            properties.insert(
-                param.name.clone(),
-                json!({
-                    "type": param.param_type,
-                    "description": param.description
-                }),
-            );
+            properties.insert(param.name.clone(), param.schema.to_value());
         }
 
         let mut schema = json!({
@@ -71,11 +390,16 @@ impl ToolMeta {
             schema["required"] = json!(self.required);
         }
 
-        Tool {
-            name: name.into(),
-            description: self.description.clone(),
-            input_schema: schema,
+        if !self.defs.is_empty() {
+            let defs: Map<String, Value> = self
+                .defs
+                .iter()
+                .map(|(name, def)| (name.clone(), def.to_value()))
+                .collect();
+            schema["$defs"] = Value::Object(defs);
         }
+
+        schema
     }
 }
 
@@ -254,3 +578,136 @@ impl Default for EndpointMeta {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_constraints() {
+        let schema = Schema::string()
+            .enum_values(["metric", "imperial"])
+            .default_value("metric")
+            .into_value();
+        assert_eq!(schema["type"], "string");
+        assert_eq!(schema["enum"], json!(["metric", "imperial"]));
+        assert_eq!(schema["default"], "metric");
+
+        let schema = Schema::integer()
+            .minimum(1)
+            .exclusive_maximum(17)
+            .into_value();
+        assert_eq!(schema["minimum"], 1);
+        assert_eq!(schema["exclusiveMaximum"], 17);
+
+        let schema = Schema::array(Schema::string().format("email"))
+            .min_items(1)
+            .unique_items(true)
+            .into_value();
+        assert_eq!(schema["items"]["format"], "email");
+        assert_eq!(schema["minItems"], 1);
+    }
+
+    #[test]
+    fn test_tool_meta_with_defs_and_one_of() {
+        let tool = ToolMeta::new()
+            .param_schema(
+                "location",
+                Schema::one_of([Schema::reference("city"), Schema::reference("coords")]),
+            )
+            .def(
+                "city",
+                Schema::object()
+                    .property("name", Schema::string().min_length(1))
+                    .required(&["name"]),
+            )
+            .def(
+                "coords",
+                Schema::object()
+                    .property("lat", Schema::number().minimum(-90).maximum(90))
+                    .property("lon", Schema::number().minimum(-180).maximum(180))
+                    .required(&["lat", "lon"])
+                    .additional_properties(false),
+            )
+            .required(&["location"])
+            .to_tool("weather");
+
+        let schema = &tool.input_schema;
+        assert_eq!(
+            schema["properties"]["location"]["oneOf"][0]["$ref"],
+            "#/$defs/city"
+        );
+        assert_eq!(schema["$defs"]["city"]["required"][0], "name");
+        assert_eq!(schema["$defs"]["coords"]["additionalProperties"], false);
+        assert_eq!(schema["required"][0], "location");
+    }
+
+    #[test]
+    fn test_tool_annotations() {
+        let tool = ToolMeta::new()
+            .title("Delete File")
+            .destructive(true)
+            .idempotent(true)
+            .to_tool("delete_file");
+        assert!(tool.is_destructive());
+
+        let value = serde_json::to_value(&tool).unwrap();
+        assert_eq!(value["title"], "Delete File");
+        assert_eq!(value["annotations"]["destructiveHint"], true);
+        assert_eq!(value["annotations"]["idempotentHint"], true);
+        assert!(value["annotations"].get("readOnlyHint").is_none());
+
+        let tool = ToolMeta::new().read_only(true).to_tool("list_files");
+        assert!(tool.is_read_only());
+        assert!(!tool.is_destructive());
+
+        let tool = ToolMeta::new().to_tool("plain");
+        assert!(tool.annotations.is_none());
+        assert!(tool.is_destructive());
+    }
+
+    #[test]
+    fn test_raw_input_schema() {
+        let raw = json!({
+            "type": "object",
+            "properties": { "q": { "type": "string" } }
+        });
+        let tool = ToolMeta::new()
+            .param("ignored", "string", "Ignored")
+            .input_schema(raw.clone())
+            .unwrap()
+            .to_tool("search");
+        assert_eq!(tool.input_schema, raw);
+
+        let tool = ToolMeta::new()
+            .input_schema(Schema::object().property("q", Schema::string()))
+            .unwrap()
+            .to_tool("search");
+        assert_eq!(tool.input_schema["properties"]["q"]["type"], "string");
+
+        // Inputs are always objects
+        assert!(ToolMeta::new()
+            .input_schema(json!({ "type": "string" }))
+            .is_err());
+        assert!(ToolMeta::new().input_schema(json!(true)).is_err());
+        assert!(ToolMeta::new()
+            .input_schema(json!({ "properties": {} }))
+            .is_err());
+
+        let schema = Schema::raw(json!({ "type": "string", "pattern": "^[a-z]+$" }));
+        assert_eq!(schema.into_value()["pattern"], "^[a-z]+$");
+    }
+
+    #[test]
+    fn test_property_needs_object_schema() {
+        let schema = Schema::any().property("q", Schema::string()).into_value();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["properties"]["q"]["type"], "string");
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot add property 'q' to a schema of type \"string\"")]
+    fn test_property_on_scalar_schema_panics() {
+        let _ = Schema::string().property("q", Schema::string());
+    }
+}
@@ -0,0 +1,334 @@
+use crate::server::HttpMcpServer;
+use actix_web::body::{BodySize, BodyStream, BoxBody, MessageBody};
+use actix_web::dev::{ResponseHead, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, ContentEncoding, HeaderMap, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::middleware::Next;
+use actix_web::web::Data;
+use actix_web::Result;
+use bytes::Bytes;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use futures::{Stream, StreamExt};
+use std::io::{self, Write};
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Codings in order of preference when the client rates several equally
+const SUPPORTED: [ContentEncoding; 4] = [
+    ContentEncoding::Brotli,
+    ContentEncoding::Zstd,
+    ContentEncoding::Gzip,
+    ContentEncoding::Deflate,
+];
+
+/// Complete bodies smaller than this are sent uncompressed
+const MIN_COMPRESS_SIZE: u64 = 1024;
+
+/// Compress responses with the coding negotiated from `Accept-Encoding`
+///
+/// Only active with [`crate::HttpMcpServerBuilder::compression`]. Complete
+/// bodies are compressed at once; streamed bodies such as SSE are flushed after
+/// every chunk, so each event reaches the client as soon as it is sent.
+pub async fn compress_responses(
+    req: ServiceRequest,
+    next: Next<BoxBody>,
+) -> Result<ServiceResponse> {
+    let enabled = req
+        .app_data::<Data<Arc<HttpMcpServer>>>()
+        .is_some_and(|server| server.compression);
+    let encoding = negotiate(req.headers());
+
+    let mut res = next.call(req).await?;
+    if !enabled || !is_compressible(res.response().head()) {
+        return Ok(res);
+    }
+    res.headers_mut()
+        .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+    let Some(encoding) = encoding else {
+        return Ok(res);
+    };
+
+    Ok(res.map_body(|head, body| match body.size() {
+        BodySize::None => body,
+        BodySize::Sized(len) if len < MIN_COMPRESS_SIZE => body,
+        BodySize::Sized(_) => match body.try_into_bytes() {
+            Ok(bytes) => match compress(encoding, &bytes) {
+                Ok(compressed) => {
+                    mark_encoded(head, encoding);
+                    BoxBody::new(compressed)
+                }
+                Err(e) => {
+                    tracing::warn!("Sending response uncompressed: {}", e);
+                    BoxBody::new(bytes)
+                }
+            },
+            Err(body) => compress_body(head, body, encoding),
+        },
+        BodySize::Stream => compress_body(head, body, encoding),
+    }))
+}
+
+fn compress_body(head: &mut ResponseHead, mut body: BoxBody, encoding: ContentEncoding) -> BoxBody {
+    mark_encoded(head, encoding);
+    let chunks = futures::stream::poll_fn(move |cx| Pin::new(&mut body).poll_next(cx));
+    BoxBody::new(BodyStream::new(compress_stream(chunks, encoding)))
+}
+
+fn mark_encoded(head: &mut ResponseHead, encoding: ContentEncoding) {
+    let headers = head.headers_mut();
+    headers.insert(header::CONTENT_ENCODING, encoding.to_header_value());
+    headers.remove(header::CONTENT_LENGTH);
+}
+
+/// Whether the response is text that is not already encoded
+fn is_compressible(head: &ResponseHead) -> bool {
+    if matches!(
+        head.status,
+        StatusCode::SWITCHING_PROTOCOLS | StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED
+    ) || head.headers().contains_key(header::CONTENT_ENCODING)
+    {
+        return false;
+    }
+    let Some(mime) = head
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+    else {
+        return false;
+    };
+    let mime = mime.trim().to_ascii_lowercase();
+    mime.starts_with("text/")
+        || ["/json", "+json", "/xml", "+xml", "/javascript"]
+            .iter()
+            .any(|suffix| mime.ends_with(suffix))
+}
+
+/// Pick the client's most preferred supported coding, honouring q-values
+pub(crate) fn negotiate(headers: &HeaderMap) -> Option<ContentEncoding> {
+    let ranges: Vec<(String, f32)> = headers
+        .get_all(header::ACCEPT_ENCODING)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let coding = parts.next()?.trim().to_ascii_lowercase();
+            let q = match parts.find_map(|p| p.trim().strip_prefix("q=")) {
+                Some(q) => q.trim().parse().ok()?,
+                None => 1.0,
+            };
+            (!coding.is_empty()).then_some((coding, q))
+        })
+        .collect();
+    let quality = |coding: &str| {
+        ranges
+            .iter()
+            .find(|(c, _)| c == coding)
+            .or_else(|| ranges.iter().find(|(c, _)| c == "*"))
+            .map_or(0.0, |(_, q)| *q)
+    };
+
+    let mut best: Option<(ContentEncoding, f32)> = None;
+    for encoding in SUPPORTED {
+        let q = quality(encoding.as_str());
+        if q > 0.0 && best.map_or(true, |(_, best_q)| q > best_q) {
+            best = Some((encoding, q));
+        }
+    }
+    best.map(|(encoding, _)| encoding)
+}
+
+/// Compress a stream, flushing after each chunk so it can be decoded right away
+pub(crate) fn compress_stream<S, E>(
+    chunks: S,
+    encoding: ContentEncoding,
+) -> impl Stream<Item = io::Result<Bytes>>
+where
+    S: Stream<Item = std::result::Result<Bytes, E>>,
+    E: std::fmt::Display,
+{
+    async_stream::try_stream! {
+        let mut encoder = Encoder::new(encoding)?;
+        futures::pin_mut!(chunks);
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk.map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+            yield encoder.write_and_flush(&chunk)?;
+        }
+        yield encoder.finish()?;
+    }
+}
+
+/// Compress a complete body
+pub(crate) fn compress(encoding: ContentEncoding, data: &[u8]) -> io::Result<Bytes> {
+    let mut encoder = Encoder::new(encoding)?;
+    encoder.writer().write_all(data)?;
+    encoder.finish()
+}
+
+enum Encoder {
+    Brotli(Box<brotli::CompressorWriter<Vec<u8>>>),
+    Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>),
+    Gzip(GzEncoder<Vec<u8>>),
+    Deflate(ZlibEncoder<Vec<u8>>),
+}
+
+impl Encoder {
+    /// Levels favour speed, as for actix-web's `Compress`
+    fn new(encoding: ContentEncoding) -> io::Result<Self> {
+        let fast = flate2::Compression::fast();
+        Ok(match encoding {
+            ContentEncoding::Brotli => Self::Brotli(Box::new(brotli::CompressorWriter::new(
+                Vec::new(),
+                32 * 1024,
+                3,
+                22,
+            ))),
+            ContentEncoding::Zstd => Self::Zstd(zstd::stream::write::Encoder::new(Vec::new(), 3)?),
+            ContentEncoding::Gzip => Self::Gzip(GzEncoder::new(Vec::new(), fast)),
+            ContentEncoding::Deflate => Self::Deflate(ZlibEncoder::new(Vec::new(), fast)),
+            other => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unsupported content coding {}", other.as_str()),
+                ))
+            }
+        })
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Self::Brotli(encoder) => encoder.as_mut(),
+            Self::Zstd(encoder) => encoder,
+            Self::Gzip(encoder) => encoder,
+            Self::Deflate(encoder) => encoder,
+        }
+    }
+
+    /// Compress `data` and return all output so far, ending on a flush point
+    fn write_and_flush(&mut self, data: &[u8]) -> io::Result<Bytes> {
+        let writer = self.writer();
+        writer.write_all(data)?;
+        writer.flush()?;
+        let output = match self {
+            Self::Brotli(encoder) => encoder.get_mut(),
+            Self::Zstd(encoder) => encoder.get_mut(),
+            Self::Gzip(encoder) => encoder.get_mut(),
+            Self::Deflate(encoder) => encoder.get_mut(),
+        };
+        Ok(Bytes::from(std::mem::take(output)))
+    }
+
+    /// End the compressed stream, returning the output not yet taken
+    fn finish(self) -> io::Result<Bytes> {
+        let output = match self {
+            Self::Brotli(encoder) => encoder.into_inner(),
+            Self::Zstd(encoder) => encoder.finish()?,
+            Self::Gzip(encoder) => encoder.finish()?,
+            Self::Deflate(encoder) => encoder.finish()?,
+        };
+        Ok(Bytes::from(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn accept(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT_ENCODING, HeaderValue::from_static(value));
+        headers
+    }
+
+    /// Collects a decoder's output while the decoder owns the writer
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Decode as much of a compressed stream as the bytes so far allow
+    fn decode_partial(encoding: ContentEncoding, data: &[u8]) -> Vec<u8> {
+        let output = Shared::default();
+        let mut decoder: Box<dyn Write> = match encoding {
+            ContentEncoding::Brotli => {
+                Box::new(brotli::DecompressorWriter::new(output.clone(), 4096))
+            }
+            ContentEncoding::Zstd => {
+                Box::new(zstd::stream::write::Decoder::new(output.clone()).unwrap())
+            }
+            ContentEncoding::Gzip => Box::new(flate2::write::GzDecoder::new(output.clone())),
+            _ => Box::new(flate2::write::ZlibDecoder::new(output.clone())),
+        };
+        decoder.write_all(data).unwrap();
+        decoder.flush().unwrap();
+        let decoded = output.0.borrow().clone();
+        decoded
+    }
+
+    #[test]
+    fn test_negotiation() {
+        assert_eq!(negotiate(&HeaderMap::new()), None);
+        assert_eq!(negotiate(&accept("gzip")), Some(ContentEncoding::Gzip));
+        assert_eq!(
+            negotiate(&accept("gzip, deflate, br, zstd")),
+            Some(ContentEncoding::Brotli)
+        );
+        assert_eq!(
+            negotiate(&accept("br;q=0.5, gzip;q=0.8")),
+            Some(ContentEncoding::Gzip)
+        );
+        assert_eq!(negotiate(&accept("*")), Some(ContentEncoding::Brotli));
+        assert_eq!(negotiate(&accept("*, br;q=0")), Some(ContentEncoding::Zstd));
+        assert_eq!(negotiate(&accept("identity, compress")), None);
+    }
+
+    #[tokio::test]
+    async fn test_stream_chunks_decode_without_waiting() {
+        let events = [
+            "event: message\ndata: one\n\n",
+            "event: message\ndata: two\n\n",
+        ];
+        for encoding in SUPPORTED {
+            let chunks = futures::stream::iter(
+                events.map(|e| Ok::<_, io::Error>(Bytes::from_static(e.as_bytes()))),
+            );
+            let output: Vec<Bytes> = compress_stream(chunks, encoding)
+                .map(|chunk| chunk.unwrap())
+                .collect()
+                .await;
+
+            // The first event decodes from the first chunk alone
+            assert_eq!(
+                decode_partial(encoding, &output[0]),
+                events[0].as_bytes(),
+                "{:?}",
+                encoding
+            );
+            assert_eq!(
+                decode_partial(encoding, &output.concat()),
+                events.concat().as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn test_compress_complete_body() {
+        let body = serde_json::json!({"text": "lorem ipsum ".repeat(500)}).to_string();
+        for encoding in SUPPORTED {
+            let compressed = compress(encoding, body.as_bytes()).unwrap();
+            assert!(compressed.len() < body.len() / 10);
+            assert_eq!(decode_partial(encoding, &compressed), body.as_bytes());
+        }
+    }
+}
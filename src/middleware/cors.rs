@@ -0,0 +1,372 @@
+use crate::error::McpError;
+use crate::server::HttpMcpServer;
+use actix_web::body::BoxBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderMap, HeaderValue};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::web::Data;
+use actix_web::{HttpResponse, Result};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Cross-origin resource sharing policy for browser-based clients
+///
+/// The default allows any origin without credentials, the usual HTTP methods and
+/// any request header, and exposes `Mcp-Session-Id` to scripts.
+///
+/// ```rust
+/// use httpmcp_rust::CorsConfig;
+/// use std::time::Duration;
+///
+/// let cors = CorsConfig::new()
+///     .allow_origin("https://app.example.com")
+///     .allow_origin_pattern("https://*.example.com")
+///     .allow_credentials(true)
+///     .max_age(Duration::from_secs(600));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// `None` allows any origin
+    origins: Option<Vec<OriginRule>>,
+    methods: Vec<String>,
+    /// `None` allows whatever headers the preflight asks for
+    headers: Option<Vec<String>>,
+    exposed_headers: Vec<String>,
+    credentials: bool,
+    max_age: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+enum OriginRule {
+    Exact(String),
+    Pattern(String),
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            origins: None,
+            methods: ["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"]
+                .map(String::from)
+                .to_vec(),
+            headers: None,
+            exposed_headers: vec!["Mcp-Session-Id".to_string()],
+            credentials: false,
+            max_age: None,
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow an exact origin such as `https://app.example.com`
+    ///
+    /// The first allowed origin or pattern replaces the default of allowing any origin.
+    pub fn allow_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origins
+            .get_or_insert_with(Vec::new)
+            .push(OriginRule::Exact(origin.into()));
+        self
+    }
+
+    /// Allow origins matching a pattern where `*` matches any run of characters,
+    /// such as `https://*.example.com`
+    pub fn allow_origin_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.origins
+            .get_or_insert_with(Vec::new)
+            .push(OriginRule::Pattern(pattern.into()));
+        self
+    }
+
+    /// Allow requests from any origin (the default)
+    pub fn allow_any_origin(mut self) -> Self {
+        self.origins = None;
+        self
+    }
+
+    /// Methods allowed in cross-origin requests
+    pub fn allow_methods(mut self, methods: &[&str]) -> Self {
+        self.methods = methods.iter().map(|m| m.to_uppercase()).collect();
+        self
+    }
+
+    /// Request headers allowed in cross-origin requests
+    pub fn allow_headers(mut self, headers: &[&str]) -> Self {
+        self.headers = Some(headers.iter().map(|h| h.to_string()).collect());
+        self
+    }
+
+    /// Allow any request header a preflight asks for (the default)
+    pub fn allow_any_header(mut self) -> Self {
+        self.headers = None;
+        self
+    }
+
+    /// Response headers readable by scripts, replacing the default `Mcp-Session-Id`
+    pub fn expose_headers(mut self, headers: &[&str]) -> Self {
+        self.exposed_headers = headers.iter().map(|h| h.to_string()).collect();
+        self
+    }
+
+    /// Allow cookies and `Authorization` headers on cross-origin requests
+    ///
+    /// The request's origin is echoed instead of `*`, as browsers require, so
+    /// credentials need an explicit list of allowed origins: the builder refuses
+    /// to build a server that allows them from any origin.
+    pub fn allow_credentials(mut self, allow: bool) -> Self {
+        self.credentials = allow;
+        self
+    }
+
+    /// How long browsers may cache a preflight response
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Refuse policies that would let any site make credentialed requests
+    pub(crate) fn validate(&self) -> crate::error::Result<()> {
+        if self.credentials && self.origins.is_none() {
+            return Err(McpError::InvalidParams(
+                "CORS credentials require an explicit list of allowed origins".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn is_origin_allowed(&self, origin: &str) -> bool {
+        match &self.origins {
+            None => true,
+            Some(rules) => rules.iter().any(|rule| match rule {
+                OriginRule::Exact(allowed) => allowed.eq_ignore_ascii_case(origin),
+                OriginRule::Pattern(pattern) => wildcard_match(pattern, origin),
+            }),
+        }
+    }
+
+    fn is_method_allowed(&self, method: &str) -> bool {
+        self.methods.iter().any(|m| m.eq_ignore_ascii_case(method))
+    }
+
+    /// Headers requested by a preflight that this policy refuses, if any
+    fn refused_headers<'a>(&self, requested: &'a str) -> Option<&'a str> {
+        let allowed = self.headers.as_ref()?;
+        requested
+            .split(',')
+            .map(str::trim)
+            .filter(|h| !h.is_empty())
+            .find(|h| !allowed.iter().any(|a| a.eq_ignore_ascii_case(h)))
+    }
+
+    fn allow_origin_header(&self, origin: &str) -> HeaderValue {
+        if self.origins.is_none() {
+            HeaderValue::from_static("*")
+        } else {
+            HeaderValue::from_str(origin).unwrap_or_else(|_| HeaderValue::from_static("null"))
+        }
+    }
+
+    fn preflight(&self, origin: &str, request_headers: &HeaderMap) -> HttpResponse {
+        let method = request_headers
+            .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        let requested = request_headers
+            .get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+
+        if !self.is_method_allowed(method) || self.refused_headers(requested).is_some() {
+            tracing::debug!("Refusing CORS preflight from {} for {}", origin, method);
+            return HttpResponse::Forbidden().finish();
+        }
+
+        let mut resp = HttpResponse::NoContent();
+        resp.insert_header((
+            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+            self.allow_origin_header(origin),
+        ))
+        .insert_header((
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            self.methods.join(", "),
+        ))
+        .insert_header((
+            header::VARY,
+            "Origin, Access-Control-Request-Method, Access-Control-Request-Headers",
+        ));
+
+        let allowed_headers = match &self.headers {
+            Some(headers) => headers.join(", "),
+            None => requested.to_string(),
+        };
+        if !allowed_headers.is_empty() {
+            resp.insert_header((header::ACCESS_CONTROL_ALLOW_HEADERS, allowed_headers));
+        }
+        if self.credentials {
+            resp.insert_header((header::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true"));
+        }
+        if let Some(max_age) = self.max_age {
+            resp.insert_header((header::ACCESS_CONTROL_MAX_AGE, max_age.as_secs()));
+        }
+        resp.finish()
+    }
+
+    fn apply(&self, origin: &str, headers: &mut HeaderMap) {
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+            self.allow_origin_header(origin),
+        );
+        headers.append(header::VARY, HeaderValue::from_static("Origin"));
+        if !self.exposed_headers.is_empty() {
+            if let Ok(value) = HeaderValue::from_str(&self.exposed_headers.join(", ")) {
+                headers.insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, value);
+            }
+        }
+        if self.credentials {
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+    }
+}
+
+/// Apply the server's [`CorsConfig`] to a resource
+///
+/// Preflight requests are answered directly; other requests from an allowed
+/// origin get the CORS response headers. Requests from other origins pass
+/// through without them, so browsers block the response.
+pub async fn apply_cors(req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse> {
+    let config = req
+        .app_data::<Data<Arc<HttpMcpServer>>>()
+        .and_then(|server| server.cors.clone());
+    let origin = req
+        .headers()
+        .get(header::ORIGIN)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let (Some(config), Some(origin)) = (config, origin) else {
+        return next.call(req).await;
+    };
+
+    let is_preflight = req.method() == Method::OPTIONS
+        && req
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+
+    if !config.is_origin_allowed(&origin) {
+        if is_preflight {
+            tracing::debug!("Refusing CORS preflight from origin {}", origin);
+            let (req, _) = req.into_parts();
+            return Ok(ServiceResponse::new(
+                req,
+                HttpResponse::Forbidden().finish(),
+            ));
+        }
+        return next.call(req).await;
+    }
+
+    if is_preflight {
+        let response = config.preflight(&origin, req.headers());
+        let (req, _) = req.into_parts();
+        return Ok(ServiceResponse::new(req, response));
+    }
+
+    let mut res = next.call(req).await?;
+    config.apply(&origin, res.headers_mut());
+    Ok(res)
+}
+
+/// Match `text` against a pattern where `*` matches any run of characters
+pub(crate) fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let text = text.to_ascii_lowercase();
+    let mut parts = pattern.split('*');
+
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No wildcard: the whole text must have matched
+        return rest.is_empty();
+    };
+
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origin_rules() {
+        let any = CorsConfig::new();
+        assert!(any.is_origin_allowed("https://anything.test"));
+
+        let config = CorsConfig::new()
+            .allow_origin("https://app.example.com")
+            .allow_origin_pattern("https://*.example.org");
+        assert!(config.is_origin_allowed("https://app.example.com"));
+        assert!(!config.is_origin_allowed("https://evil.example.com"));
+        assert!(config.is_origin_allowed("https://a.b.example.org"));
+        assert!(!config.is_origin_allowed("https://example.org.evil.test"));
+        assert!(!config.is_origin_allowed("http://a.example.org"));
+    }
+
+    #[test]
+    fn test_credentials_need_listed_origins() {
+        assert!(CorsConfig::new()
+            .allow_credentials(true)
+            .validate()
+            .is_err());
+        assert!(CorsConfig::new()
+            .allow_origin("https://app.example.com")
+            .allow_credentials(true)
+            .allow_any_origin()
+            .validate()
+            .is_err());
+        assert!(CorsConfig::new()
+            .allow_origin("https://app.example.com")
+            .allow_credentials(true)
+            .validate()
+            .is_ok());
+        assert!(CorsConfig::new().validate().is_ok());
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match(
+            "http://localhost:*",
+            "http://localhost:3000"
+        ));
+        assert!(wildcard_match("*", "https://x"));
+        assert!(!wildcard_match("https://*.test", "https://x.test.evil"));
+        assert!(wildcard_match("exact", "exact"));
+        assert!(!wildcard_match("exact", "exactly"));
+    }
+
+    #[test]
+    fn test_refused_headers() {
+        let config = CorsConfig::new().allow_headers(&["Content-Type", "Mcp-Session-Id"]);
+        assert_eq!(config.refused_headers("content-type, mcp-session-id"), None);
+        assert_eq!(
+            config.refused_headers("content-type, x-secret"),
+            Some("x-secret")
+        );
+        assert_eq!(CorsConfig::new().refused_headers("x-anything"), None);
+    }
+}
@@ -1,7 +1,25 @@
-use actix_web::http::header;
-use actix_web::{HttpResponse, Result};
+mod compress;
+mod cors;
+mod origin;
+
+pub use compress::compress_responses;
+pub use cors::{apply_cors, CorsConfig};
+pub use origin::validate_origin;
+pub(crate) use origin::RebindingGuard;
+
+use crate::error::{McpError, Result as McpResult};
+use crate::server::HttpMcpServer;
+use actix_web::body::BoxBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderMap, HeaderValue};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::web::Data;
+use actix_web::Result;
+use std::sync::Arc;
 
 /// CORS middleware configuration
+#[deprecated(note = "configure CORS with `HttpMcpServerBuilder::cors` and `CorsConfig`")]
 pub fn cors_middleware() -> actix_web::middleware::DefaultHeaders {
     actix_web::middleware::DefaultHeaders::new()
         .add((header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"))
@@ -12,30 +30,149 @@ pub fn cors_middleware() -> actix_web::middleware::DefaultHeaders {
         ))
 }
 
-/// Request validation middleware
-pub async fn validate_request(
-    req: actix_web::dev::ServiceRequest,
-    next: actix_web_lab::middleware::Next<actix_web::body::BoxBody>,
-) -> Result<actix_web::dev::ServiceResponse> {
-    // Validate Content-Type for POST requests
-    if req.method() == actix_web::http::Method::POST {
-        if let Some(content_type) = req.headers().get(header::CONTENT_TYPE) {
-            if let Ok(ct) = content_type.to_str() {
-                if !ct.contains("application/json") {
-                    let (req, _) = req.into_parts();
-                    let response = HttpResponse::BadRequest().json(serde_json::json!({
-                        "jsonrpc": "2.0",
-                        "error": {
-                            "code": -32600,
-                            "message": "Invalid Content-Type. Expected application/json"
-                        },
-                        "id": null
-                    }));
-                    return Ok(actix_web::dev::ServiceResponse::new(req, response));
-                }
+/// Enforce the Streamable HTTP `Accept` and `Content-Type` rules on the MCP endpoint
+///
+/// POST requests must send a JSON body (415 otherwise) and accept both JSON and
+/// SSE responses; GET requests must accept SSE (406 otherwise). With
+/// [`crate::HttpMcpServerBuilder::lenient_content_negotiation`] missing headers
+/// are tolerated and POST clients may accept only one of the two response types.
+pub async fn validate_request(req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse> {
+    let lenient = req
+        .app_data::<Data<Arc<HttpMcpServer>>>()
+        .is_some_and(|server| server.lenient_content_negotiation);
+
+    if let Err(e) = check_headers(req.method(), req.headers(), lenient) {
+        tracing::debug!("Rejecting {} request: {}", req.method(), e);
+        let (req, _) = req.into_parts();
+        return Ok(ServiceResponse::new(req, e.to_http_response(None)));
+    }
+
+    next.call(req).await
+}
+
+pub(crate) fn check_headers(method: &Method, headers: &HeaderMap, lenient: bool) -> McpResult<()> {
+    let has_accept = headers.contains_key(header::ACCEPT);
+    let accepts_json = accepts(headers, "application/json");
+    let accepts_sse = accepts(headers, "text/event-stream");
+
+    if method == Method::POST {
+        match headers.get(header::CONTENT_TYPE) {
+            Some(content_type) if !is_json(content_type) => {
+                return Err(McpError::UnsupportedMediaType(
+                    "Content-Type must be application/json".to_string(),
+                ));
+            }
+            None if !lenient => {
+                return Err(McpError::UnsupportedMediaType(
+                    "Content-Type must be application/json".to_string(),
+                ));
             }
+            _ => {}
+        }
+
+        let acceptable = if lenient {
+            !has_accept || accepts_json || accepts_sse
+        } else {
+            accepts_json && accepts_sse
+        };
+        if !acceptable {
+            return Err(McpError::NotAcceptable(
+                "Accept must include application/json and text/event-stream".to_string(),
+            ));
         }
+    } else if method == Method::GET && !accepts_sse && (has_accept || !lenient) {
+        return Err(McpError::NotAcceptable(
+            "Accept must include text/event-stream".to_string(),
+        ));
     }
 
-    next.call(req).await
+    Ok(())
+}
+
+/// Whether any `Accept` header covers the media type, including wildcards
+fn accepts(headers: &HeaderMap, media_type: &str) -> bool {
+    let main_type = media_type.split('/').next().unwrap_or_default();
+    headers
+        .get_all(header::ACCEPT)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|range| range.split(';').next().unwrap_or_default().trim())
+        .any(|range| {
+            range == "*/*"
+                || range.eq_ignore_ascii_case(media_type)
+                || range
+                    .strip_suffix("/*")
+                    .is_some_and(|t| t.eq_ignore_ascii_case(main_type))
+        })
+}
+
+fn is_json(content_type: &HeaderValue) -> bool {
+    content_type
+        .to_str()
+        .ok()
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(header::HeaderName, &'static str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.append(name.clone(), HeaderValue::from_static(value));
+        }
+        map
+    }
+
+    #[test]
+    fn test_post_negotiation() {
+        let spec = headers(&[
+            (header::CONTENT_TYPE, "application/json; charset=utf-8"),
+            (header::ACCEPT, "application/json, text/event-stream"),
+        ]);
+        assert!(check_headers(&Method::POST, &spec, false).is_ok());
+
+        let json_only = headers(&[
+            (header::CONTENT_TYPE, "application/json"),
+            (header::ACCEPT, "application/json"),
+        ]);
+        assert!(matches!(
+            check_headers(&Method::POST, &json_only, false),
+            Err(McpError::NotAcceptable(_))
+        ));
+        assert!(check_headers(&Method::POST, &json_only, true).is_ok());
+
+        let wildcard = headers(&[
+            (header::CONTENT_TYPE, "application/json"),
+            (header::ACCEPT, "*/*"),
+        ]);
+        assert!(check_headers(&Method::POST, &wildcard, false).is_ok());
+
+        let text = headers(&[
+            (header::CONTENT_TYPE, "text/plain"),
+            (header::ACCEPT, "application/json, text/event-stream"),
+        ]);
+        assert!(matches!(
+            check_headers(&Method::POST, &text, true),
+            Err(McpError::UnsupportedMediaType(_))
+        ));
+
+        assert!(check_headers(&Method::POST, &HeaderMap::new(), false).is_err());
+        assert!(check_headers(&Method::POST, &HeaderMap::new(), true).is_ok());
+    }
+
+    #[test]
+    fn test_get_negotiation() {
+        let sse = headers(&[(header::ACCEPT, "text/event-stream")]);
+        assert!(check_headers(&Method::GET, &sse, false).is_ok());
+
+        let json = headers(&[(header::ACCEPT, "application/json")]);
+        assert!(check_headers(&Method::GET, &json, false).is_err());
+        assert!(check_headers(&Method::GET, &json, true).is_err());
+
+        assert!(check_headers(&Method::GET, &HeaderMap::new(), false).is_err());
+        assert!(check_headers(&Method::GET, &HeaderMap::new(), true).is_ok());
+    }
 }
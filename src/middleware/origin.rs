@@ -0,0 +1,180 @@
+use super::cors::wildcard_match;
+use crate::error::{McpError, Result as McpResult};
+use crate::server::HttpMcpServer;
+use actix_web::body::BoxBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderMap};
+use actix_web::middleware::Next;
+use actix_web::web::Data;
+use actix_web::Result;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+const LOOPBACK_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "[::1]"];
+
+/// Allowed `Origin` and `Host` header values, guarding against DNS rebinding
+///
+/// `None` means the header is not checked. Values are exact or `*` patterns.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RebindingGuard {
+    pub(crate) origins: Option<Vec<String>>,
+    pub(crate) hosts: Option<Vec<String>>,
+}
+
+impl RebindingGuard {
+    /// Fill unset lists with loopback-only values when `addr` is a loopback address
+    pub(crate) fn with_loopback_defaults(mut self, addr: &str) -> Self {
+        if !is_loopback_addr(addr) {
+            return self;
+        }
+        self.hosts.get_or_insert_with(|| {
+            LOOPBACK_HOSTS
+                .iter()
+                .flat_map(|host| [host.to_string(), format!("{}:*", host)])
+                .collect()
+        });
+        self.origins.get_or_insert_with(|| {
+            LOOPBACK_HOSTS
+                .iter()
+                .flat_map(|host| {
+                    ["http", "https"].into_iter().flat_map(move |scheme| {
+                        [
+                            format!("{}://{}", scheme, host),
+                            format!("{}://{}:*", scheme, host),
+                        ]
+                    })
+                })
+                .collect()
+        });
+        self
+    }
+
+    /// Check a request's headers; `authority` is the request URI's authority,
+    /// which HTTP/2 clients send instead of a `Host` header
+    pub(crate) fn check(&self, headers: &HeaderMap, authority: Option<&str>) -> McpResult<()> {
+        if let Some(origins) = &self.origins {
+            // Requests without an Origin do not come from a browser page
+            if let Some(origin) = headers.get(header::ORIGIN) {
+                let origin = origin.to_str().unwrap_or_default();
+                if !matches_any(origins, origin) {
+                    return Err(McpError::OriginNotAllowed(origin.to_string()));
+                }
+            }
+        }
+
+        if let Some(hosts) = &self.hosts {
+            let host = headers
+                .get(header::HOST)
+                .and_then(|v| v.to_str().ok())
+                .or(authority)
+                .unwrap_or_default();
+            if !matches_any(hosts, host) {
+                return Err(McpError::HostNotAllowed(host.to_string()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn matches_any(patterns: &[String], value: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| wildcard_match(pattern, value))
+}
+
+/// Whether a bind address such as `127.0.0.1:8080` or `localhost:3000` is loopback-only
+fn is_loopback_addr(addr: &str) -> bool {
+    let host = match addr.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => addr,
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Reject requests whose `Origin` or `Host` is not allowed with 403
+///
+/// Runs before CORS, authentication and handlers, so a page on a rebinding
+/// domain cannot reach a server listening on localhost.
+pub async fn validate_origin(req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse> {
+    let result = match req.app_data::<Data<Arc<HttpMcpServer>>>() {
+        Some(server) => server
+            .rebinding_guard
+            .check(req.headers(), req.uri().authority().map(|a| a.as_str())),
+        None => Ok(()),
+    };
+
+    if let Err(e) = result {
+        tracing::warn!("Rejecting request: {}", e);
+        let (req, _) = req.into_parts();
+        return Ok(ServiceResponse::new(req, e.to_http_response(None)));
+    }
+
+    next.call(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::HeaderValue;
+
+    fn headers(host: &'static str, origin: Option<&'static str>) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        map.insert(header::HOST, HeaderValue::from_static(host));
+        if let Some(origin) = origin {
+            map.insert(header::ORIGIN, HeaderValue::from_static(origin));
+        }
+        map
+    }
+
+    #[test]
+    fn test_loopback_detection() {
+        assert!(is_loopback_addr("127.0.0.1:8080"));
+        assert!(is_loopback_addr("localhost:3000"));
+        assert!(is_loopback_addr("[::1]:8080"));
+        assert!(!is_loopback_addr("0.0.0.0:8080"));
+        assert!(!is_loopback_addr("example.com:80"));
+    }
+
+    #[test]
+    fn test_loopback_defaults() {
+        let guard = RebindingGuard::default().with_loopback_defaults("127.0.0.1:8080");
+        assert!(guard.check(&headers("localhost:8080", None), None).is_ok());
+        assert!(guard
+            .check(
+                &headers("127.0.0.1:8080", Some("http://localhost:5173")),
+                None
+            )
+            .is_ok());
+        assert!(matches!(
+            guard.check(&headers("evil.example.com:8080", None), None),
+            Err(McpError::HostNotAllowed(_))
+        ));
+        assert!(matches!(
+            guard.check(
+                &headers("localhost:8080", Some("https://evil.example.com")),
+                None
+            ),
+            Err(McpError::OriginNotAllowed(_))
+        ));
+
+        let public = RebindingGuard::default().with_loopback_defaults("0.0.0.0:8080");
+        assert!(public
+            .check(&headers("anything", Some("https://x")), None)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_http2_authority() {
+        let guard = RebindingGuard::default().with_loopback_defaults("127.0.0.1:8080");
+        assert!(guard
+            .check(&HeaderMap::new(), Some("localhost:8080"))
+            .is_ok());
+        assert!(matches!(
+            guard.check(&HeaderMap::new(), Some("evil.example.com:8080")),
+            Err(McpError::HostNotAllowed(_))
+        ));
+    }
+}
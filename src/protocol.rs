@@ -1,3 +1,4 @@
+use crate::error::{McpError, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -134,6 +135,19 @@ pub struct ResourceContents {
     pub blob: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourcesSubscribeParams {
+    pub uri: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceTemplatesListResult {
+    #[serde(rename = "resourceTemplates")]
+    pub resource_templates: Vec<ResourceTemplate>,
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceTemplate {
     #[serde(rename = "uriTemplate")]
@@ -160,9 +174,51 @@ pub struct ToolsListResult {
 pub struct Tool {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(rename = "inputSchema")]
     pub input_schema: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<ToolAnnotations>,
+}
+
+impl Tool {
+    /// Whether the tool declares that it does not modify its environment
+    pub fn is_read_only(&self) -> bool {
+        self.annotations
+            .as_ref()
+            .and_then(|a| a.read_only_hint)
+            .unwrap_or(false)
+    }
+
+    /// Whether the tool may perform destructive updates
+    ///
+    /// Follows the MCP defaults: a tool that is not read-only is assumed
+    /// destructive unless it sets `destructiveHint: false`.
+    pub fn is_destructive(&self) -> bool {
+        !self.is_read_only()
+            && self
+                .annotations
+                .as_ref()
+                .and_then(|a| a.destructive_hint)
+                .unwrap_or(true)
+    }
+}
+
+/// Hints describing tool behavior; clients must treat them as untrusted
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ToolAnnotations {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(rename = "readOnlyHint", skip_serializing_if = "Option::is_none")]
+    pub read_only_hint: Option<bool>,
+    #[serde(rename = "destructiveHint", skip_serializing_if = "Option::is_none")]
+    pub destructive_hint: Option<bool>,
+    #[serde(rename = "idempotentHint", skip_serializing_if = "Option::is_none")]
+    pub idempotent_hint: Option<bool>,
+    #[serde(rename = "openWorldHint", skip_serializing_if = "Option::is_none")]
+    pub open_world_hint: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -179,20 +235,8 @@ pub struct ToolsCallResult {
     pub is_error: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type")]
-pub enum ToolContent {
-    #[serde(rename = "text")]
-    Text { text: String },
-    #[serde(rename = "image")]
-    Image {
-        data: String,
-        #[serde(rename = "mimeType")]
-        mime_type: String,
-    },
-    #[serde(rename = "resource")]
-    Resource { resource: ResourceContents },
-}
+/// Content returned by tools
+pub type ToolContent = ContentBlock;
 
 // ============================================================================
 // Prompts
@@ -249,19 +293,332 @@ pub struct PromptMessage {
     pub content: PromptContent,
 }
 
+/// Content of a prompt message
+pub type PromptContent = ContentBlock;
+
+// ============================================================================
+// Content
+// ============================================================================
+
+/// A block of content in a tool result or prompt message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
-pub enum PromptContent {
+pub enum ContentBlock {
     #[serde(rename = "text")]
-    Text { text: String },
+    Text {
+        text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
     #[serde(rename = "image")]
     Image {
         data: String,
         #[serde(rename = "mimeType")]
         mime_type: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
+    #[serde(rename = "audio")]
+    Audio {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
+    #[serde(rename = "resource_link")]
+    ResourceLink {
+        uri: String,
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+        mime_type: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
+    #[serde(rename = "resource")]
+    Resource {
+        resource: ResourceContents,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
     },
 }
 
+/// Hints for clients about how content should be used or displayed
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Annotations {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audience: Option<Vec<Role>>,
+    /// Importance from 0.0 (least) to 1.0 (most)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<f64>,
+    /// ISO 8601 timestamp of the last modification
+    #[serde(rename = "lastModified", skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+impl ContentBlock {
+    pub fn text(text: impl Into<String>) -> Self {
+        ContentBlock::Text {
+            text: text.into(),
+            annotations: None,
+        }
+    }
+
+    /// Image from already base64-encoded data
+    pub fn image(data: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        ContentBlock::Image {
+            data: data.into(),
+            mime_type: mime_type.into(),
+            annotations: None,
+        }
+    }
+
+    /// Image from raw bytes; the MIME type is sniffed from the data
+    ///
+    /// Fails if the data is not in a recognised image format.
+    pub fn image_bytes(bytes: &[u8]) -> Result<Self> {
+        let mime_type = sniff_image_type(bytes).ok_or_else(|| {
+            McpError::InternalError("Image data is not in a recognised image format".to_string())
+        })?;
+        Ok(Self::image(encode_base64(bytes), mime_type))
+    }
+
+    /// Audio from already base64-encoded data
+    pub fn audio(data: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        ContentBlock::Audio {
+            data: data.into(),
+            mime_type: mime_type.into(),
+            annotations: None,
+        }
+    }
+
+    /// Audio from raw bytes; the MIME type is sniffed from the data
+    ///
+    /// Fails if the data is not in a recognised audio format. MP4 and 3GPP
+    /// containers are taken to hold audio, whatever their brand.
+    pub fn audio_bytes(bytes: &[u8]) -> Result<Self> {
+        let mime_type = sniff_audio_type(bytes).ok_or_else(|| {
+            McpError::InternalError("Audio data is not in a recognised audio format".to_string())
+        })?;
+        Ok(Self::audio(encode_base64(bytes), mime_type))
+    }
+
+    /// Link to a resource the client can fetch with `resources/read`
+    pub fn resource_link(uri: impl Into<String>, name: impl Into<String>) -> Self {
+        ContentBlock::ResourceLink {
+            uri: uri.into(),
+            name: name.into(),
+            title: None,
+            description: None,
+            mime_type: None,
+            size: None,
+            annotations: None,
+        }
+    }
+
+    /// Link to a registered resource, copying its metadata
+    pub fn link_to(resource: &Resource) -> Self {
+        ContentBlock::ResourceLink {
+            uri: resource.uri.clone(),
+            name: resource.name.clone(),
+            title: None,
+            description: resource.description.clone(),
+            mime_type: resource.mime_type.clone(),
+            size: None,
+            annotations: None,
+        }
+    }
+
+    /// Resource contents embedded inline
+    pub fn embedded(resource: ResourceContents) -> Self {
+        ContentBlock::Resource {
+            resource,
+            annotations: None,
+        }
+    }
+
+    /// Attach annotations to this block
+    pub fn with_annotations(mut self, new: Annotations) -> Self {
+        match &mut self {
+            ContentBlock::Text { annotations, .. }
+            | ContentBlock::Image { annotations, .. }
+            | ContentBlock::Audio { annotations, .. }
+            | ContentBlock::ResourceLink { annotations, .. }
+            | ContentBlock::Resource { annotations, .. } => *annotations = Some(new),
+        }
+        self
+    }
+
+    pub fn annotations(&self) -> Option<&Annotations> {
+        match self {
+            ContentBlock::Text { annotations, .. }
+            | ContentBlock::Image { annotations, .. }
+            | ContentBlock::Audio { annotations, .. }
+            | ContentBlock::ResourceLink { annotations, .. }
+            | ContentBlock::Resource { annotations, .. } => annotations.as_ref(),
+        }
+    }
+}
+
+impl Annotations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn audience(mut self, audience: impl IntoIterator<Item = Role>) -> Self {
+        self.audience = Some(audience.into_iter().collect());
+        self
+    }
+
+    /// Set priority, clamped to the 0.0..=1.0 range
+    pub fn priority(mut self, priority: f64) -> Self {
+        self.priority = Some(priority.clamp(0.0, 1.0));
+        self
+    }
+
+    pub fn last_modified(mut self, timestamp: impl Into<String>) -> Self {
+        self.last_modified = Some(timestamp.into());
+        self
+    }
+}
+
+impl ResourceContents {
+    /// Text contents for a resource
+    pub fn text(uri: impl Into<String>, mime_type: Option<&str>, text: impl Into<String>) -> Self {
+        Self {
+            uri: uri.into(),
+            mime_type: mime_type.map(|m| m.to_string()),
+            text: Some(text.into()),
+            blob: None,
+        }
+    }
+
+    /// Binary contents for a resource, base64-encoded with a sniffed MIME type
+    pub fn blob(uri: impl Into<String>, bytes: &[u8]) -> Self {
+        Self {
+            uri: uri.into(),
+            mime_type: sniff_mime_type(bytes).map(|m| m.to_string()),
+            text: None,
+            blob: Some(encode_base64(bytes)),
+        }
+    }
+}
+
+/// Guess the MIME type of binary data from its leading bytes
+pub fn sniff_mime_type(bytes: &[u8]) -> Option<&'static str> {
+    let starts = |magic: &[u8]| bytes.starts_with(magic);
+    let riff = |kind: &[u8]| bytes.len() >= 12 && starts(b"RIFF") && &bytes[8..12] == kind;
+
+    if starts(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if starts(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if starts(b"GIF87a") || starts(b"GIF89a") {
+        Some("image/gif")
+    } else if riff(b"WEBP") {
+        Some("image/webp")
+    } else if is_bmp(bytes) {
+        Some("image/bmp")
+    } else if riff(b"WAVE") {
+        Some("audio/wav")
+    } else if starts(b"ID3") || is_mpeg_frame(bytes) {
+        Some("audio/mpeg")
+    } else if starts(b"OggS") {
+        Some("audio/ogg")
+    } else if starts(b"fLaC") {
+        Some("audio/flac")
+    } else if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" {
+        iso_media_type(bytes)
+    } else if starts(b"%PDF-") {
+        Some("application/pdf")
+    } else if starts(b"PK\x03\x04") {
+        Some("application/zip")
+    } else {
+        None
+    }
+}
+
+/// Type of image data, if it is in a recognised image format
+fn sniff_image_type(bytes: &[u8]) -> Option<&'static str> {
+    sniff_mime_type(bytes).filter(|mime_type| mime_type.starts_with("image/"))
+}
+
+/// Type of audio data, if it is in a recognised audio format
+///
+/// Audio-only MP4 files often carry a video brand such as `isom` or `mp42`.
+fn sniff_audio_type(bytes: &[u8]) -> Option<&'static str> {
+    match sniff_mime_type(bytes)? {
+        "video/mp4" | "audio/mp4" => Some("audio/mp4"),
+        "video/3gpp" => Some("audio/3gpp"),
+        "video/3gpp2" => Some("audio/3gpp2"),
+        mime_type if mime_type.starts_with("audio/") => Some(mime_type),
+        _ => None,
+    }
+}
+
+/// An MPEG audio frame header: 11 sync bits, then a version and layer that are not reserved
+///
+/// Layer bits `00` are reserved in MPEG audio but used by AAC's ADTS headers.
+fn is_mpeg_frame(bytes: &[u8]) -> bool {
+    let [0xFF, header, ..] = bytes else {
+        return false;
+    };
+    let version = (header >> 3) & 0x03;
+    let layer = (header >> 1) & 0x03;
+    header & 0xE0 == 0xE0 && version != 0x01 && layer != 0x00
+}
+
+/// A BMP file header followed by one of the known DIB header sizes
+fn is_bmp(bytes: &[u8]) -> bool {
+    if bytes.len() < 18 || !bytes.starts_with(b"BM") {
+        return false;
+    }
+    let dib_size = u32::from_le_bytes([bytes[14], bytes[15], bytes[16], bytes[17]]);
+    matches!(dib_size, 12 | 16 | 40 | 52 | 56 | 64 | 108 | 124)
+}
+
+/// Type of an ISO base media file (MP4, HEIF, AVIF, ...) from its `ftyp` box
+fn iso_media_type(bytes: &[u8]) -> Option<&'static str> {
+    let box_size = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+    let compatible = bytes
+        .get(16..box_size.min(bytes.len()))
+        .unwrap_or_default()
+        .chunks_exact(4);
+    let compatible_with = |brand: &[u8]| compatible.clone().any(|b| b == brand);
+
+    match &bytes[8..12] {
+        b"avif" | b"avis" => Some("image/avif"),
+        b"mif1" | b"msf1" if compatible_with(b"avif") => Some("image/avif"),
+        b"heic" | b"heix" | b"heim" | b"heis" | b"hevc" | b"hevx" => Some("image/heic"),
+        b"mif1" | b"msf1" => Some("image/heif"),
+        b"M4A " | b"M4B " | b"M4P " => Some("audio/mp4"),
+        b"qt  " => Some("video/quicktime"),
+        b"3gp4" | b"3gp5" | b"3gp6" | b"3gp7" => Some("video/3gpp"),
+        b"3g2a" | b"3g2b" | b"3g2c" => Some("video/3gpp2"),
+        b"isom" | b"iso2" | b"iso4" | b"iso5" | b"iso6" | b"mp41" | b"mp42" | b"avc1" | b"dash"
+        | b"M4V " | b"MSNV" => Some("video/mp4"),
+        _ => None,
+    }
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
 // ============================================================================
 // Logging
 // ============================================================================
@@ -271,11 +628,159 @@ pub struct LoggingSetLevelParams {
     pub level: LogLevel,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "lowercase")]
 pub enum LogLevel {
     Debug,
     Info,
+    Notice,
     Warning,
     Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+/// Params of a `notifications/message` log notification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingMessageParams {
+    pub level: LogLevel,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logger: Option<String>,
+    pub data: Value,
+}
+
+// ============================================================================
+// Progress
+// ============================================================================
+
+/// Params of a `notifications/progress` notification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressParams {
+    #[serde(rename = "progressToken")]
+    pub progress_token: Value,
+    pub progress: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_content_serialization() {
+        let content = ContentBlock::text("hello")
+            .with_annotations(Annotations::new().audience([Role::User]).priority(0.8));
+        assert_eq!(
+            serde_json::to_value(&content).unwrap(),
+            json!({
+                "type": "text",
+                "text": "hello",
+                "annotations": { "audience": ["user"], "priority": 0.8 }
+            })
+        );
+
+        let link = ContentBlock::resource_link("file:///a.txt", "a.txt");
+        assert_eq!(
+            serde_json::to_value(&link).unwrap(),
+            json!({ "type": "resource_link", "uri": "file:///a.txt", "name": "a.txt" })
+        );
+
+        let parsed: ContentBlock = serde_json::from_value(
+            json!({ "type": "audio", "data": "AAAA", "mimeType": "audio/wav" }),
+        )
+        .unwrap();
+        assert!(
+            matches!(parsed, ContentBlock::Audio { ref mime_type, .. } if mime_type == "audio/wav")
+        );
+    }
+
+    #[test]
+    fn test_bytes_helpers() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        match ContentBlock::image_bytes(png).unwrap() {
+            ContentBlock::Image {
+                data, mime_type, ..
+            } => {
+                assert_eq!(mime_type, "image/png");
+                assert_eq!(data, "iVBORw0KGgoAAAANSUhEUg==");
+            }
+            other => panic!("unexpected content: {:?}", other),
+        }
+
+        let wav = b"RIFF\x24\0\0\0WAVEfmt ";
+        assert_eq!(sniff_mime_type(wav), Some("audio/wav"));
+
+        // Content blocks refuse data of the other kind or of no known kind
+        assert!(ContentBlock::image_bytes(wav).is_err());
+        assert!(ContentBlock::audio_bytes(png).is_err());
+        assert!(ContentBlock::image_bytes(b"plain text").is_err());
+        assert!(ContentBlock::audio_bytes(b"%PDF-1.7").is_err());
+        assert!(matches!(
+            ContentBlock::audio_bytes(wav),
+            Ok(ContentBlock::Audio { ref mime_type, .. }) if mime_type == "audio/wav"
+        ));
+
+        // MPEG frames need a layer; AAC's ADTS header has none
+        assert_eq!(
+            sniff_mime_type(&[0xFF, 0xFB, 0x90, 0x00]),
+            Some("audio/mpeg")
+        );
+        assert_eq!(sniff_mime_type(&[0xFF, 0xF1, 0x50, 0x80]), None);
+        assert_eq!(sniff_mime_type(&[0xFF, 0xEB, 0x90, 0x00]), None);
+        assert_eq!(sniff_mime_type(b"plain text"), None);
+        assert_eq!(sniff_mime_type(b"BMW owners manual"), None);
+        let bmp = b"BM\x46\0\0\0\0\0\0\0\x36\0\0\0\x28\0\0\0";
+        assert_eq!(sniff_mime_type(bmp), Some("image/bmp"));
+
+        let ftyp = |major: &[u8], compatible: &[u8]| {
+            let size = (16 + compatible.len()) as u32;
+            let mut data = size.to_be_bytes().to_vec();
+            data.extend_from_slice(b"ftyp");
+            data.extend_from_slice(major);
+            data.extend_from_slice(&[0, 0, 0, 0]);
+            data.extend_from_slice(compatible);
+            data
+        };
+        assert_eq!(
+            sniff_mime_type(&ftyp(b"isom", b"isomavc1")),
+            Some("video/mp4")
+        );
+        assert_eq!(
+            sniff_mime_type(&ftyp(b"M4A ", b"M4A mp42")),
+            Some("audio/mp4")
+        );
+        assert_eq!(
+            sniff_mime_type(&ftyp(b"heic", b"mif1heic")),
+            Some("image/heic")
+        );
+        assert_eq!(
+            sniff_mime_type(&ftyp(b"avif", b"avifmif1")),
+            Some("image/avif")
+        );
+        assert_eq!(
+            sniff_mime_type(&ftyp(b"mif1", b"mif1avif")),
+            Some("image/avif")
+        );
+        assert_eq!(sniff_mime_type(&ftyp(b"mif1", b"mif1")), Some("image/heif"));
+        assert_eq!(sniff_mime_type(&ftyp(b"crx ", b"")), None);
+        assert_eq!(
+            sniff_audio_type(&ftyp(b"isom", b"isommp41")),
+            Some("audio/mp4")
+        );
+        assert_eq!(
+            sniff_audio_type(&ftyp(b"3gp5", b"3gp5")),
+            Some("audio/3gpp")
+        );
+        assert_eq!(sniff_audio_type(&ftyp(b"heic", b"mif1heic")), None);
+        assert_eq!(sniff_image_type(&ftyp(b"isom", b"isomavc1")), None);
+
+        let blob = ResourceContents::blob("file:///doc.pdf", b"%PDF-1.7");
+        assert_eq!(blob.mime_type.as_deref(), Some("application/pdf"));
+        assert_eq!(blob.blob.as_deref(), Some("JVBERi0xLjc="));
+    }
 }
@@ -1,29 +1,103 @@
 use crate::auth::OAuthConfig;
+use crate::config::ServerConfig;
+use crate::context::RequestContext;
+use crate::error::{ErrorStatusPolicy, McpError, Result};
 use crate::handler_types::{
-    RegisteredEndpoint, RegisteredMultipartEndpoint, RegisteredPrompt, RegisteredResource,
-    RegisteredTool,
+    InitializedHook, IntoToolResult, RegisteredEndpoint, RegisteredMultipartEndpoint,
+    RegisteredPrompt, RegisteredResource, RegisteredTool, SessionAttributesHook, SessionClosedHook,
+    ToolPolicy,
 };
-use crate::jsonrpc::JsonRpcResponse;
+use crate::handlers::{PromptProvider, ResourceProvider, ToolProvider};
+use crate::jsonrpc::{JsonRpcMessage, JsonRpcRequest, JsonRpcResponse, RequestId};
 use crate::metadata::{EndpointMeta, PromptMeta, ResourceMeta, ToolMeta};
-use crate::protocol::{Implementation, ServerCapabilities};
+use crate::middleware::{CorsConfig, RebindingGuard};
+use crate::protocol::{Implementation, ResourcesCapability, ServerCapabilities};
+use crate::session::{Session, SessionCloseReason, SessionStore};
+use crate::shutdown::{termination_signal, ServerHandle, ShutdownState};
 use crate::transport::create_app;
 use actix_web::{middleware::Logger, App, HttpServer};
+use serde_json::Value;
 use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::future::Future;
+use std::net::TcpListener;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// Default limit for JSON-RPC request bodies
+pub const DEFAULT_MAX_PAYLOAD_SIZE: usize = 2 * 1024 * 1024;
+
+/// Default time a graceful shutdown waits for in-flight requests
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default time [`HttpMcpServer::send_request`] waits for the client's response
+pub const DEFAULT_CLIENT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(60);
+
+type PendingRequest = (String, oneshot::Sender<JsonRpcResponse>);
+
+/// Forgets a server-to-client request once its caller stops waiting
+struct PendingRequestGuard<'a> {
+    pending: &'a Mutex<HashMap<RequestId, PendingRequest>>,
+    id: RequestId,
+}
+
+impl Drop for PendingRequestGuard<'_> {
+    fn drop(&mut self) {
+        self.pending.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Where [`HttpMcpServer::start_on`] accepts connections
+enum Listener {
+    Addr(String),
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Uds(std::os::unix::net::UnixListener, PathBuf),
+}
 
 /// Main HTTP MCP Server
 pub struct HttpMcpServer {
     pub(crate) server_info: Implementation,
     pub(crate) capabilities: ServerCapabilities,
     pub(crate) tools: HashMap<String, RegisteredTool>,
+    pub(crate) tool_policy: Option<ToolPolicy>,
     pub(crate) resources: HashMap<String, RegisteredResource>,
     pub(crate) prompts: HashMap<String, RegisteredPrompt>,
+    pub(crate) tool_providers: Vec<Arc<dyn ToolProvider>>,
+    pub(crate) resource_providers: Vec<Arc<dyn ResourceProvider>>,
+    pub(crate) prompt_providers: Vec<Arc<dyn PromptProvider>>,
     pub(crate) endpoints: Vec<RegisteredEndpoint>,
     pub(crate) multipart_endpoints: Vec<RegisteredMultipartEndpoint>,
     pub(crate) oauth_config: Option<OAuthConfig>,
-    pub(crate) enable_cors: bool,
-    pub(crate) response_tx: broadcast::Sender<JsonRpcResponse>,
+    pub(crate) cors: Option<CorsConfig>,
+    pub(crate) rebinding_guard: RebindingGuard,
+    pub(crate) sessions: SessionStore,
+    pub(crate) legacy_sse: bool,
+    pub(crate) websocket: bool,
+    pub(crate) compression: bool,
+    /// Outbound channels of sessions: the GET `/mcp` stream, legacy SSE, WebSocket and stdio
+    pub(crate) session_streams: RwLock<HashMap<String, mpsc::UnboundedSender<JsonRpcMessage>>>,
+    /// Server-to-client requests awaiting a response, with the session they were sent to
+    pub(crate) pending_requests: Mutex<HashMap<RequestId, PendingRequest>>,
+    pub(crate) client_response_timeout: Duration,
+    pub(crate) require_sessions: bool,
+    pub(crate) sse_responses: bool,
+    pub(crate) error_status: ErrorStatusPolicy,
+    pub(crate) config: ServerConfig,
+    pub(crate) shutdown_timeout: Duration,
+    pub(crate) shutdown_state: ShutdownState,
+    #[cfg(feature = "tls")]
+    pub(crate) tls: Option<crate::tls::TlsConfig>,
+    pub(crate) lenient_content_negotiation: bool,
+    pub(crate) session_attributes: Option<SessionAttributesHook>,
+    pub(crate) on_initialized: Option<InitializedHook>,
+    pub(crate) on_session_closed: Option<SessionClosedHook>,
+    pub(crate) session_idle_timeout: Option<Duration>,
+    pub(crate) session_max_lifetime: Option<Duration>,
+    reaper_started: AtomicBool,
 }
 
 impl HttpMcpServer {
@@ -32,21 +106,471 @@ impl HttpMcpServer {
         HttpMcpServerBuilder::new()
     }
 
-    /// Run the server on the specified address
+    /// Look up an active session by ID
+    pub fn session(&self, id: &str) -> Option<Session> {
+        self.sessions.get(id)
+    }
+
+    /// Close a session, ending its SSE streams and cancelling its in-flight requests
+    ///
+    /// Returns false if no such session exists.
+    pub async fn close_session(&self, id: &str) -> bool {
+        self.end_session(id, SessionCloseReason::Server)
+            .await
+            .is_some()
+    }
+
+    /// Set an attribute on an open session, for requests that follow
+    ///
+    /// Returns false if no such session exists.
+    pub fn set_session_attribute(
+        &self,
+        session_id: &str,
+        key: impl Into<String>,
+        value: Value,
+    ) -> bool {
+        self.sessions.set_attribute(session_id, key, value)
+    }
+
+    /// Handle a JSON-RPC message without going through a transport
+    ///
+    /// Runs the same session checks and handlers as the HTTP endpoint, so the
+    /// server can sit behind another gateway or be tested without HTTP.
+    /// `ctx.session_id` plays the part of the `Mcp-Session-Id` header; an
+    /// `initialize` with an unknown ID starts a session under that ID.
+    /// Notifications return `None`, and failures become JSON-RPC errors. The
+    /// request is validated like a POST body, so a `jsonrpc` other than `"2.0"`
+    /// or an empty method is answered with -32600.
+    ///
+    /// ```rust,no_run
+    /// # use httpmcp_rust::jsonrpc::{JsonRpcRequest, RequestId};
+    /// # use httpmcp_rust::{HttpMcpServer, RequestContext};
+    /// # use actix_web::http::header::HeaderMap;
+    /// # async fn example(server: HttpMcpServer) {
+    /// let ctx = RequestContext::new(HeaderMap::new(), "POST".into(), "/gateway".into(), None);
+    /// let request = JsonRpcRequest::new("tools/list", None, Some(RequestId::Number(1)));
+    /// let response = server.handle_message(request, ctx).await;
+    /// # }
+    /// ```
+    pub async fn handle_message(
+        &self,
+        request: JsonRpcRequest,
+        ctx: RequestContext,
+    ) -> Option<JsonRpcResponse> {
+        crate::transport::handle_message(self, request, ctx).await
+    }
+
+    /// Send a notification to a session's client
+    ///
+    /// Returns false if the session has no open GET `/mcp`, WebSocket, legacy SSE
+    /// or stdio stream.
+    pub fn notify(&self, session_id: &str, method: impl Into<String>, params: Value) -> bool {
+        let notification = JsonRpcRequest::new(method, Some(params), None);
+        self.send_to_session(session_id, notification.into())
+    }
+
+    /// Send a request to a session's client and wait for its response
+    ///
+    /// Only sessions with an open outbound stream (GET `/mcp`, WebSocket, legacy
+    /// SSE, stdio) can receive requests. Streamable HTTP clients POST their
+    /// response to `/mcp`. A JSON-RPC error from the client is returned as
+    /// [`McpError::ClientError`]. If the client does not answer within the
+    /// [client response timeout](HttpMcpServerBuilder::client_response_timeout),
+    /// the request is cancelled with `notifications/cancelled` and
+    /// [`McpError::ClientTimeout`] is returned.
+    pub async fn send_request(
+        &self,
+        session_id: &str,
+        method: impl Into<String>,
+        params: Option<Value>,
+    ) -> Result<Value> {
+        // Random IDs keep other sessions from guessing and answering this request
+        let id = RequestId::String(uuid::Uuid::new_v4().to_string());
+        let (tx, rx) = oneshot::channel();
+        self.pending_requests
+            .lock()
+            .unwrap()
+            .insert(id.clone(), (session_id.to_string(), tx));
+        let _pending = PendingRequestGuard {
+            pending: &self.pending_requests,
+            id: id.clone(),
+        };
+
+        let method = method.into();
+        let request = JsonRpcRequest::new(method.clone(), params, Some(id.clone()));
+        if !self.send_to_session(session_id, request.into()) {
+            return Err(McpError::SessionNotFound(session_id.to_string()));
+        }
+
+        let response = match tokio::time::timeout(self.client_response_timeout, rx).await {
+            Ok(response) => {
+                response.map_err(|_| McpError::SessionNotFound(session_id.to_string()))?
+            }
+            Err(_) => {
+                let reason = format!("No response within {:?}", self.client_response_timeout);
+                self.notify(
+                    session_id,
+                    "notifications/cancelled",
+                    serde_json::json!({"requestId": id, "reason": reason}),
+                );
+                return Err(McpError::ClientTimeout(method));
+            }
+        };
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(McpError::ClientError(error)),
+            (Some(result), None) => Ok(result),
+            (None, None) => Ok(Value::Null),
+        }
+    }
+
+    /// Hand a client's response to the [`Self::send_request`] call waiting for it
+    ///
+    /// Responses are only accepted from the session the request was sent to.
+    pub(crate) fn complete_request(&self, session_id: &str, response: JsonRpcResponse) {
+        let waiting = response.id.as_ref().and_then(|id| {
+            let mut pending = self.pending_requests.lock().unwrap();
+            match pending.get(id) {
+                Some((owner, _)) if owner == session_id => pending.remove(id),
+                _ => None,
+            }
+        });
+        match waiting {
+            Some((_, tx)) => {
+                let _ = tx.send(response);
+            }
+            None => tracing::debug!(
+                "Ignoring response to unknown request {:?} from session {}",
+                response.id,
+                session_id
+            ),
+        }
+    }
+
+    fn send_to_session(&self, session_id: &str, message: JsonRpcMessage) -> bool {
+        self.session_streams
+            .read()
+            .unwrap()
+            .get(session_id)
+            .is_some_and(|tx| tx.send(message).is_ok())
+    }
+
+    /// Fail the [`Self::send_request`] calls still waiting on a session's client
+    pub(crate) fn fail_pending_requests(&self, session_id: &str) {
+        // Dropping the senders wakes the callers with an error
+        self.pending_requests
+            .lock()
+            .unwrap()
+            .retain(|_, (owner, _)| owner != session_id);
+    }
+
+    pub(crate) async fn end_session(
+        &self,
+        id: &str,
+        reason: SessionCloseReason,
+    ) -> Option<Session> {
+        let session = self.sessions.close(id)?;
+        self.session_streams.write().unwrap().remove(id);
+        self.fail_pending_requests(id);
+        tracing::debug!("Session {} closed: {:?}", id, reason);
+
+        if let Some(hook) = &self.on_session_closed {
+            if let Err(e) = hook(session.clone(), reason).await {
+                tracing::warn!("on_session_closed hook failed: {}", e);
+            }
+        }
+
+        Some(session)
+    }
+
+    /// Close every session past its idle timeout or maximum lifetime
+    pub(crate) async fn reap_expired_sessions(&self) {
+        let expired = self
+            .sessions
+            .expired(self.session_idle_timeout, self.session_max_lifetime);
+        for (id, reason) in expired {
+            self.end_session(&id, reason).await;
+        }
+    }
+
+    /// Start the background task that expires sessions, if any timeout is configured
+    ///
+    /// Only the first call within a Tokio runtime starts the task, so every
+    /// transport and mounted worker can call this. The task holds a weak
+    /// reference and stops once the server is dropped.
+    pub(crate) fn spawn_session_reaper(server: &Arc<Self>) {
+        let shortest = match (server.session_idle_timeout, server.session_max_lifetime) {
+            (Some(idle), Some(max)) => idle.min(max),
+            (Some(timeout), None) | (None, Some(timeout)) => timeout,
+            (None, None) => return,
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        if server.reaper_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let period = (shortest / 2).clamp(Duration::from_secs(1), Duration::from_secs(60));
+        let server = Arc::downgrade(server);
+
+        runtime.spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                let Some(server) = server.upgrade() else {
+                    break;
+                };
+                server.reap_expired_sessions().await;
+            }
+        });
+    }
+
+    /// Serve MCP over stdin and stdout for desktop clients
+    ///
+    /// Messages are newline-delimited JSON-RPC and use the same tools, resources
+    /// and prompts as the HTTP transport. Returns when stdin closes. Stdout carries
+    /// only protocol messages, so send logs to stderr, for example with
+    /// `tracing_subscriber::fmt().with_writer(std::io::stderr).init()`.
+    pub async fn run_stdio(self) -> std::io::Result<()> {
+        tracing::info!("Starting MCP server on stdio");
+        let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+        crate::stdio::serve(Arc::new(self), stdin, tokio::io::stdout()).await
+    }
+
+    /// Run the server on the specified address until Ctrl-C or SIGTERM
+    ///
+    /// When `addr` is a loopback address and no allowed hosts or origins were
+    /// configured, only `localhost`, `127.0.0.1` and `[::1]` are accepted in the
+    /// `Host` and `Origin` headers. The signal triggers a graceful shutdown as
+    /// described on [`ServerHandle::shutdown`].
     pub async fn run(self, addr: impl Into<String>) -> std::io::Result<()> {
-        let addr = addr.into();
+        self.run_until(addr, termination_signal()).await
+    }
+
+    /// Run the server until `signal` resolves, then shut down gracefully
+    ///
+    /// ```rust,no_run
+    /// # use httpmcp_rust::HttpMcpServer;
+    /// # async fn example(server: HttpMcpServer, stop: tokio::sync::oneshot::Receiver<()>) {
+    /// server
+    ///     .run_until("127.0.0.1:8080", async {
+    ///         let _ = stop.await;
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub async fn run_until(
+        self,
+        addr: impl Into<String>,
+        signal: impl Future<Output = ()>,
+    ) -> std::io::Result<()> {
+        self.start(addr)?.serve_until(signal).await
+    }
+
+    /// Run the server on an already bound TCP listener until Ctrl-C or SIGTERM
+    pub async fn run_listener(self, listener: TcpListener) -> std::io::Result<()> {
+        self.start_listener(listener)?
+            .serve_until(termination_signal())
+            .await
+    }
+
+    /// Run the server on a Unix domain socket until Ctrl-C or SIGTERM
+    ///
+    /// See [`Self::start_uds`].
+    #[cfg(unix)]
+    pub async fn run_uds(self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        self.start_uds(path)?
+            .serve_until(termination_signal())
+            .await
+    }
+
+    /// Start serving on the specified address and return a handle to stop it
+    ///
+    /// Must be called within a Tokio runtime. Unlike [`Self::run`], no signal
+    /// handlers are installed. Bind port 0 and read [`ServerHandle::addrs`] to
+    /// learn the port that was picked.
+    pub fn start(self, addr: impl Into<String>) -> std::io::Result<ServerHandle> {
+        self.start_on(Listener::Addr(addr.into()))
+    }
+
+    /// Start serving on an already bound TCP listener
+    ///
+    /// The loopback `Host` and `Origin` defaults of [`Self::run`] apply when the
+    /// listener is bound to a loopback address.
+    pub fn start_listener(self, listener: TcpListener) -> std::io::Result<ServerHandle> {
+        self.start_on(Listener::Tcp(listener))
+    }
+
+    /// Start serving plain HTTP on a Unix domain socket
+    ///
+    /// A stale socket already at `path`, one that refuses connections, is
+    /// replaced. A socket another server is listening on, or any other file,
+    /// fails with `AddrInUse` and is left untouched. The socket is removed when the
+    /// server stops. Browsers cannot reach the socket, so
+    /// no `Host` or `Origin` defaults are applied, and
+    /// [`RequestContext::remote_addr`] is `None` for its requests. TLS is not
+    /// supported on Unix domain sockets.
+    #[cfg(unix)]
+    pub fn start_uds(self, path: impl AsRef<Path>) -> std::io::Result<ServerHandle> {
+        use std::os::unix::fs::FileTypeExt;
+
+        #[cfg(feature = "tls")]
+        if self.tls.is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "TLS is not supported on Unix domain sockets",
+            ));
+        }
+        let path = path.as_ref();
+        match std::fs::symlink_metadata(path) {
+            Ok(meta) if meta.file_type().is_socket() => {
+                // Only a socket nobody is listening on is stale
+                match std::os::unix::net::UnixStream::connect(path) {
+                    Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                        std::fs::remove_file(path)?
+                    }
+                    _ => {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::AddrInUse,
+                            format!("{} is in use by another server", path.display()),
+                        ))
+                    }
+                }
+            }
+            Ok(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AddrInUse,
+                    format!("{} exists and is not a socket", path.display()),
+                ))
+            }
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            Err(_) => {}
+        }
+        let listener = std::os::unix::net::UnixListener::bind(path)?;
+        self.start_on(Listener::Uds(listener, path.to_path_buf()))
+    }
+
+    fn start_on(mut self, listener: Listener) -> std::io::Result<ServerHandle> {
+        let description = match &listener {
+            Listener::Addr(addr) => addr.clone(),
+            Listener::Tcp(listener) => listener.local_addr()?.to_string(),
+            #[cfg(unix)]
+            Listener::Uds(_, path) => path.display().to_string(),
+        };
+        // Browsers cannot reach a Unix domain socket, so it needs no loopback defaults
+        if matches!(listener, Listener::Addr(_) | Listener::Tcp(_)) {
+            self.rebinding_guard = self.rebinding_guard.with_loopback_defaults(&description);
+        }
         let server = Arc::new(self);
 
-        tracing::info!("Starting MCP server on {}", addr);
+        #[cfg(feature = "tls")]
+        let tls = server
+            .tls
+            .as_ref()
+            .map(|tls| tls.server_config())
+            .transpose()?;
+        #[cfg(not(feature = "tls"))]
+        let tls: Option<()> = None;
 
-        HttpServer::new(move || {
+        tracing::info!("Starting MCP server on {}", description);
+        Self::spawn_session_reaper(&server);
+
+        let app_server = server.clone();
+        let mut http = HttpServer::new(move || {
             App::new()
                 .wrap(Logger::default())
-                .configure(|cfg| create_app(cfg, server.clone()))
+                .configure(|cfg| create_app(cfg, app_server.clone()))
         })
-        .bind(&addr)?
-        .run()
-        .await
+        .disable_signals()
+        .shutdown_timeout(server.shutdown_timeout.as_secs());
+
+        let config = &server.config;
+        if let Some(workers) = config.workers {
+            http = http.workers(workers);
+        }
+        if let Some(keep_alive) = config.keep_alive {
+            http = http.keep_alive(keep_alive);
+        }
+        if let Some(timeout) = config.client_request_timeout {
+            http = http.client_request_timeout(timeout);
+        }
+        if let Some(timeout) = config.client_disconnect_timeout {
+            http = http.client_disconnect_timeout(timeout);
+        }
+        if let Some(connections) = config.max_connections {
+            http = http.max_connections(connections);
+        }
+        if let Some(backlog) = config.backlog {
+            http = http.backlog(backlog);
+        }
+        #[cfg(feature = "tls")]
+        let http = http.on_connect(crate::tls::on_connect);
+
+        let (http, addrs) = match (listener, tls) {
+            #[cfg(feature = "tls")]
+            (Listener::Addr(addr), Some(config)) => {
+                let http = http.bind_rustls_0_23(addr, config)?;
+                let addrs = http.addrs();
+                (http, addrs)
+            }
+            #[cfg(feature = "tls")]
+            (Listener::Tcp(listener), Some(config)) => {
+                let http = http.listen_rustls_0_23(listener, config)?;
+                let addrs = http.addrs();
+                (http, addrs)
+            }
+            #[cfg(unix)]
+            (Listener::Uds(..), Some(_)) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "TLS is not supported on Unix domain sockets",
+                ))
+            }
+            (Listener::Addr(addr), _) => {
+                let http = http.bind(addr)?;
+                let addrs = http.addrs();
+                (http, addrs)
+            }
+            (Listener::Tcp(listener), _) => {
+                let http = http.listen(listener)?;
+                let addrs = http.addrs();
+                (http, addrs)
+            }
+            // actix-web reports a placeholder address for Unix sockets
+            #[cfg(unix)]
+            (Listener::Uds(listener, _), None) => (http.listen_uds(listener)?, Vec::new()),
+        };
+
+        Ok(ServerHandle::spawn(server, http.run(), addrs))
+    }
+
+    /// Shut down gracefully without stopping any HTTP listener
+    ///
+    /// New requests and streams are refused with 503 at once. In-flight requests
+    /// get up to `grace` to finish; then open streams receive a final `shutdown`
+    /// event and every session is closed, cancelling requests still running.
+    /// [`ServerHandle::shutdown`] calls this while stopping the listener; call it
+    /// directly when the server is mounted in another application or served over
+    /// tower or stdio.
+    pub async fn shutdown(&self, grace: Duration) {
+        if self.shutdown_state.begin_drain() {
+            tracing::info!("Shutting down, waiting up to {:?} for requests", grace);
+        }
+        if tokio::time::timeout(grace, self.shutdown_state.drained())
+            .await
+            .is_err()
+        {
+            tracing::warn!(
+                "Cancelling {} requests still running after {:?}",
+                self.shutdown_state.in_flight(),
+                grace
+            );
+        }
+
+        self.shutdown_state.close();
+        for id in self.sessions.ids() {
+            self.end_session(&id, SessionCloseReason::Shutdown).await;
+        }
     }
 }
 
@@ -55,12 +579,34 @@ pub struct HttpMcpServerBuilder {
     name: String,
     version: String,
     tools: HashMap<String, RegisteredTool>,
+    tool_policy: Option<ToolPolicy>,
     resources: HashMap<String, RegisteredResource>,
     prompts: HashMap<String, RegisteredPrompt>,
+    tool_providers: Vec<Arc<dyn ToolProvider>>,
+    resource_providers: Vec<Arc<dyn ResourceProvider>>,
+    prompt_providers: Vec<Arc<dyn PromptProvider>>,
     endpoints: Vec<RegisteredEndpoint>,
     multipart_endpoints: Vec<RegisteredMultipartEndpoint>,
     oauth_config: Option<OAuthConfig>,
-    enable_cors: bool,
+    cors: Option<CorsConfig>,
+    rebinding_guard: RebindingGuard,
+    legacy_sse: bool,
+    websocket: bool,
+    compression: bool,
+    require_sessions: bool,
+    sse_responses: bool,
+    error_status: ErrorStatusPolicy,
+    config: ServerConfig,
+    shutdown_timeout: Duration,
+    client_response_timeout: Duration,
+    #[cfg(feature = "tls")]
+    tls: Option<crate::tls::TlsConfig>,
+    lenient_content_negotiation: bool,
+    session_attributes: Option<SessionAttributesHook>,
+    on_initialized: Option<InitializedHook>,
+    on_session_closed: Option<SessionClosedHook>,
+    session_idle_timeout: Option<Duration>,
+    session_max_lifetime: Option<Duration>,
 }
 
 impl HttpMcpServerBuilder {
@@ -69,12 +615,34 @@ impl HttpMcpServerBuilder {
             name: "httpmcp-server".to_string(),
             version: "1.0.0".to_string(),
             tools: HashMap::new(),
+            tool_policy: None,
             resources: HashMap::new(),
             prompts: HashMap::new(),
+            tool_providers: Vec::new(),
+            resource_providers: Vec::new(),
+            prompt_providers: Vec::new(),
             endpoints: Vec::new(),
             multipart_endpoints: Vec::new(),
             oauth_config: None,
-            enable_cors: true,
+            cors: Some(CorsConfig::default()),
+            rebinding_guard: RebindingGuard::default(),
+            legacy_sse: false,
+            websocket: false,
+            compression: false,
+            require_sessions: true,
+            sse_responses: true,
+            error_status: ErrorStatusPolicy::default(),
+            config: ServerConfig::default(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            client_response_timeout: DEFAULT_CLIENT_RESPONSE_TIMEOUT,
+            #[cfg(feature = "tls")]
+            tls: None,
+            lenient_content_negotiation: false,
+            session_attributes: None,
+            on_initialized: None,
+            on_session_closed: None,
+            session_idle_timeout: None,
+            session_max_lifetime: None,
         }
     }
 
@@ -91,23 +659,67 @@ impl HttpMcpServerBuilder {
     }
 
     /// Register a tool with handler
-    pub fn tool<F, Fut>(mut self, name: impl Into<String>, meta: ToolMeta, handler: F) -> Self
+    ///
+    /// The handler may return any type implementing [`IntoToolResult`], such as a
+    /// JSON `Value`, a `String`, one or more `ToolContent` blocks or a full `ToolsCallResult`.
+    pub fn tool<F, Fut, R>(mut self, name: impl Into<String>, meta: ToolMeta, handler: F) -> Self
     where
         F: Fn(HashMap<String, serde_json::Value>, crate::context::RequestContext) -> Fut
             + Send
             + Sync
             + 'static,
-        Fut: std::future::Future<Output = crate::error::Result<serde_json::Value>> + Send + 'static,
+        Fut: std::future::Future<Output = crate::error::Result<R>> + Send + 'static,
+        R: IntoToolResult + 'static,
     {
         let name_str = name.into();
         let tool = RegisteredTool {
             meta: meta.to_tool(name_str.clone()),
-            handler: Box::new(move |args, ctx| Box::pin(handler(args, ctx))),
+            handler: Box::new(move |args, ctx| {
+                let fut = handler(args, ctx);
+                Box::pin(async move { fut.await?.into_tool_result() })
+            }),
         };
         self.tools.insert(name_str, tool);
         self
     }
 
+    /// Set a policy that decides whether a tool may be called
+    ///
+    /// The policy sees the tool's metadata, including its annotations, and the
+    /// request context with the session's attributes. For example, to refuse
+    /// destructive tools in sessions a viewer token initialized:
+    ///
+    /// ```rust,no_run
+    /// # use httpmcp_rust::{HttpMcpServer, McpError};
+    /// # use serde_json::json;
+    /// # use std::collections::HashMap;
+    /// let builder = HttpMcpServer::builder()
+    ///     .session_attributes(|_params, ctx| {
+    ///         let read_only = ctx.get_bearer_token().as_deref() == Some("viewer-token");
+    ///         Ok(HashMap::from([("read_only".to_string(), json!(read_only))]))
+    ///     })
+    ///     .tool_policy(|tool, ctx| {
+    ///         let read_only = ctx.session_attribute("read_only") == Some(&json!(true));
+    ///         if read_only && tool.is_destructive() {
+    ///             return Err(McpError::AuthorizationFailed(format!(
+    ///                 "Tool '{}' is not allowed in read-only sessions",
+    ///                 tool.name
+    ///             )));
+    ///         }
+    ///         Ok(())
+    ///     });
+    /// ```
+    pub fn tool_policy<F>(mut self, policy: F) -> Self
+    where
+        F: Fn(&crate::protocol::Tool, &crate::context::RequestContext) -> crate::error::Result<()>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.tool_policy = Some(Arc::new(policy));
+        self
+    }
+
     /// Register a resource with list and read handlers
     pub fn resource<FL, FR, FutL, FutR>(
         mut self,
@@ -162,6 +774,34 @@ impl HttpMcpServerBuilder {
         self
     }
 
+    /// Register a tool provider
+    ///
+    /// Provider tools are listed after function-registered tools. A function-registered
+    /// tool with the same name takes precedence on `tools/call`.
+    pub fn tool_provider(mut self, provider: impl ToolProvider + 'static) -> Self {
+        self.tool_providers.push(Arc::new(provider));
+        self
+    }
+
+    /// Register a resource provider
+    ///
+    /// Providers also serve `resources/templates/list`, and `resources/subscribe`
+    /// and `resources/unsubscribe` when they report `supports_subscribe`.
+    /// `read_resource` and `subscribe` should return `McpError::ResourceNotFound`
+    /// for URIs they do not own.
+    pub fn resource_provider(mut self, provider: impl ResourceProvider + 'static) -> Self {
+        self.resource_providers.push(Arc::new(provider));
+        self
+    }
+
+    /// Register a prompt provider
+    ///
+    /// `get_prompt` should return `McpError::PromptNotFound` for names it does not own.
+    pub fn prompt_provider(mut self, provider: impl PromptProvider + 'static) -> Self {
+        self.prompt_providers.push(Arc::new(provider));
+        self
+    }
+
     /// Register a custom HTTP endpoint
     pub fn endpoint<F, Fut>(mut self, meta: EndpointMeta, handler: F) -> Self
     where
@@ -229,35 +869,242 @@ impl HttpMcpServerBuilder {
     }
 
     /// Enable or disable CORS
+    ///
+    /// Enabled by default with [`CorsConfig::default`]; enabling keeps a policy
+    /// already set with [`Self::cors`].
     pub fn enable_cors(mut self, enable: bool) -> Self {
-        self.enable_cors = enable;
+        if !enable {
+            self.cors = None;
+        } else if self.cors.is_none() {
+            self.cors = Some(CorsConfig::default());
+        }
+        self
+    }
+
+    /// Origins allowed in the `Origin` header, as exact values or `*` patterns
+    ///
+    /// Requests from other origins get 403 before any handler or authentication
+    /// runs, protecting local servers from DNS rebinding. Requests without an
+    /// `Origin` header (non-browser clients) are allowed.
+    pub fn allowed_origins(mut self, origins: &[&str]) -> Self {
+        self.rebinding_guard.origins = Some(origins.iter().map(|o| o.to_string()).collect());
+        self
+    }
+
+    /// Values allowed in the `Host` header, such as `localhost:*` or `mcp.example.com`
+    ///
+    /// Requests for other hosts get 403 before any handler or authentication runs.
+    pub fn allowed_hosts(mut self, hosts: &[&str]) -> Self {
+        self.rebinding_guard.hosts = Some(hosts.iter().map(|h| h.to_string()).collect());
+        self
+    }
+
+    /// Set the CORS policy for the MCP endpoint and custom endpoints
+    pub fn cors(mut self, config: CorsConfig) -> Self {
+        self.cors = Some(config);
+        self
+    }
+
+    /// Require every request other than `initialize` and `ping` to belong to an
+    /// initialized session (default: true)
+    ///
+    /// Disable to run a stateless server: `initialize` issues no
+    /// `Mcp-Session-Id`, and requests without one are served without lifecycle
+    /// checks.
+    pub fn require_sessions(mut self, require: bool) -> Self {
+        self.require_sessions = require;
+        self
+    }
+
+    /// Also serve the 2024-11-05 HTTP+SSE transport for older clients
+    ///
+    /// Clients open `GET /sse`, receive an `endpoint` event naming
+    /// `/messages?sessionId=...`, and POST requests there; responses and
+    /// notifications arrive on the SSE stream. Disabled by default.
+    pub fn legacy_sse(mut self, enable: bool) -> Self {
+        self.legacy_sse = enable;
+        self
+    }
+
+    /// Serve bidirectional JSON-RPC over a WebSocket at `GET /mcp/ws`
+    ///
+    /// Each connection is one session. Responses, notifications and requests
+    /// from [`HttpMcpServer::send_request`] are delivered on the socket.
+    /// Disabled by default.
+    pub fn websocket(mut self, enable: bool) -> Self {
+        self.websocket = enable;
+        self
+    }
+
+    /// Compress responses with brotli, zstd, gzip or deflate as negotiated by
+    /// `Accept-Encoding`
+    ///
+    /// JSON bodies under 1 KiB are sent as is. SSE streams are flushed after
+    /// every event so clients never wait on the compressor. Disabled by default.
+    pub fn compression(mut self, enable: bool) -> Self {
+        self.compression = enable;
+        self
+    }
+
+    /// Answer POST requests with an SSE stream when the client accepts `text/event-stream`
+    ///
+    /// The stream carries progress and log notifications for the request followed by
+    /// its response. Enabled by default; when disabled every response is plain JSON.
+    pub fn sse_responses(mut self, enable: bool) -> Self {
+        self.sse_responses = enable;
+        self
+    }
+
+    /// Choose the HTTP status for responses carrying a JSON-RPC error
+    ///
+    /// Defaults to [`ErrorStatusPolicy::Ok`], so clients read failures from the
+    /// response body. Transport errors keep their own status either way.
+    pub fn error_status(mut self, policy: ErrorStatusPolicy) -> Self {
+        self.error_status = policy;
+        self
+    }
+
+    /// Maximum size in bytes of a JSON-RPC request body (default 2 MiB)
+    ///
+    /// Larger requests are rejected with HTTP 413 and a JSON-RPC error. Shorthand
+    /// for [`ServerConfig::mcp_payload_limit`].
+    pub fn max_payload_size(mut self, bytes: usize) -> Self {
+        self.config.mcp_payload_limit = bytes;
+        self
+    }
+
+    /// Tune the HTTP server and set body limits per kind of endpoint
+    ///
+    /// Replaces any limit set earlier with [`Self::max_payload_size`].
+    pub fn server_config(mut self, config: ServerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// How long a graceful shutdown waits for in-flight requests (default 30 seconds)
+    pub fn shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// How long [`HttpMcpServer::send_request`] waits for the client's response
+    /// before failing with [`McpError::ClientTimeout`] (default 60 seconds)
+    pub fn client_response_timeout(mut self, timeout: Duration) -> Self {
+        self.client_response_timeout = timeout;
+        self
+    }
+
+    /// Serve HTTPS instead of plain HTTP, optionally verifying client certificates
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, config: crate::tls::TlsConfig) -> Self {
+        self.tls = Some(config);
+        self
+    }
+
+    /// Relax `Accept` and `Content-Type` checks on the MCP endpoint for older clients
+    ///
+    /// By default POST requests must send `Content-Type: application/json` and accept
+    /// both `application/json` and `text/event-stream`, and GET requests must accept
+    /// `text/event-stream`. In lenient mode missing headers are tolerated and POST
+    /// clients may accept just one of the two response types.
+    pub fn lenient_content_negotiation(mut self, lenient: bool) -> Self {
+        self.lenient_content_negotiation = lenient;
+        self
+    }
+
+    /// Compute a session's attributes when its client initializes
+    ///
+    /// The hook sees the `initialize` request and its context, including headers
+    /// and any client certificate. Returning an error refuses the initialization.
+    /// The attributes are then available to every request of the session as
+    /// [`RequestContext::session_attributes`](crate::RequestContext::session_attributes).
+    pub fn session_attributes<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(
+                &crate::protocol::InitializeParams,
+                &crate::context::RequestContext,
+            ) -> crate::error::Result<HashMap<String, Value>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.session_attributes = Some(Arc::new(hook));
+        self
+    }
+
+    /// Register a hook called when a session's client sends `notifications/initialized`
+    pub fn on_initialized<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn(crate::protocol::Implementation, crate::context::RequestContext) -> Fut
+            + Send
+            + Sync
+            + 'static,
+        Fut: std::future::Future<Output = crate::error::Result<()>> + Send + 'static,
+    {
+        self.on_initialized = Some(Arc::new(move |client_info, ctx| {
+            Box::pin(hook(client_info, ctx))
+        }));
+        self
+    }
+
+    /// Register a hook called after a session is closed by the client, a timeout or the server
+    pub fn on_session_closed<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn(Session, SessionCloseReason) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = crate::error::Result<()>> + Send + 'static,
+    {
+        self.on_session_closed = Some(Arc::new(move |session, reason| {
+            Box::pin(hook(session, reason))
+        }));
+        self
+    }
+
+    /// Close sessions that receive no requests for this long
+    pub fn session_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.session_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Close sessions this long after initialization, regardless of activity
+    pub fn session_max_lifetime(mut self, lifetime: Duration) -> Self {
+        self.session_max_lifetime = Some(lifetime);
         self
     }
 
     /// Build the server
+    ///
+    /// Fails if the CORS policy allows credentials from any origin.
     pub fn build(self) -> crate::error::Result<HttpMcpServer> {
+        if let Some(cors) = &self.cors {
+            cors.validate()?;
+        }
+
         let capabilities = ServerCapabilities {
             logging: Some(Default::default()),
-            prompts: if self.prompts.is_empty() {
+            prompts: if self.prompts.is_empty() && self.prompt_providers.is_empty() {
                 None
             } else {
                 Some(Default::default())
             },
-            resources: if self.resources.is_empty() {
+            resources: if self.resources.is_empty() && self.resource_providers.is_empty() {
                 None
             } else {
-                Some(Default::default())
+                Some(ResourcesCapability {
+                    subscribe: self
+                        .resource_providers
+                        .iter()
+                        .any(|provider| provider.supports_subscribe())
+                        .then_some(true),
+                    list_changed: None,
+                })
             },
-            tools: if self.tools.is_empty() {
+            tools: if self.tools.is_empty() && self.tool_providers.is_empty() {
                 None
             } else {
                 Some(Default::default())
             },
         };
 
-        // Create broadcast channel for SSE responses
-        let (response_tx, _) = broadcast::channel(100);
-
         Ok(HttpMcpServer {
             server_info: Implementation {
                 name: self.name,
@@ -265,13 +1112,39 @@ impl HttpMcpServerBuilder {
             },
             capabilities,
             tools: self.tools,
+            tool_policy: self.tool_policy,
             resources: self.resources,
             prompts: self.prompts,
+            tool_providers: self.tool_providers,
+            resource_providers: self.resource_providers,
+            prompt_providers: self.prompt_providers,
             endpoints: self.endpoints,
             multipart_endpoints: self.multipart_endpoints,
             oauth_config: self.oauth_config,
-            enable_cors: self.enable_cors,
-            response_tx,
+            cors: self.cors,
+            rebinding_guard: self.rebinding_guard,
+            sessions: SessionStore::new(),
+            legacy_sse: self.legacy_sse,
+            websocket: self.websocket,
+            compression: self.compression,
+            session_streams: RwLock::new(HashMap::new()),
+            pending_requests: Mutex::new(HashMap::new()),
+            require_sessions: self.require_sessions,
+            sse_responses: self.sse_responses,
+            error_status: self.error_status,
+            config: self.config,
+            shutdown_timeout: self.shutdown_timeout,
+            client_response_timeout: self.client_response_timeout,
+            shutdown_state: ShutdownState::default(),
+            #[cfg(feature = "tls")]
+            tls: self.tls,
+            lenient_content_negotiation: self.lenient_content_negotiation,
+            session_attributes: self.session_attributes,
+            on_initialized: self.on_initialized,
+            on_session_closed: self.on_session_closed,
+            session_idle_timeout: self.session_idle_timeout,
+            session_max_lifetime: self.session_max_lifetime,
+            reaper_started: AtomicBool::new(false),
         })
     }
 }
@@ -0,0 +1,237 @@
+use crate::protocol::Implementation;
+use crate::version::ProtocolVersion;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// Header carrying the session ID assigned at initialization
+pub const SESSION_ID_HEADER: &str = "mcp-session-id";
+
+/// Lifecycle state of a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    /// No `initialize` request has been handled yet
+    Uninitialized,
+    /// `initialize` succeeded; waiting for `notifications/initialized`
+    Initializing,
+    /// Normal operation
+    Ready,
+    /// The session was terminated and accepts no further requests
+    Closed,
+}
+
+/// Why a session was closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionCloseReason {
+    /// The client sent `DELETE` with its session ID
+    Terminated,
+    /// No request arrived within the idle timeout
+    IdleTimeout,
+    /// The session outlived its maximum lifetime
+    MaxLifetime,
+    /// The server closed the session via [`crate::HttpMcpServer::close_session`]
+    Server,
+    /// The server shut down
+    Shutdown,
+}
+
+/// State kept for each MCP session
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// Session ID sent to the client in the `Mcp-Session-Id` header
+    pub id: String,
+
+    /// Protocol revision negotiated during initialization
+    pub protocol_version: ProtocolVersion,
+
+    /// Client name and version from the `initialize` request
+    pub client_info: Option<Implementation>,
+
+    /// Current lifecycle state
+    pub state: SessionState,
+
+    /// When the session was initialized
+    pub created_at: Instant,
+
+    /// When the session last received a request
+    pub last_activity: Instant,
+
+    /// Application-defined attributes, such as a read-only flag
+    ///
+    /// Set at initialization by the builder's
+    /// [`session_attributes`](crate::HttpMcpServerBuilder::session_attributes) hook
+    /// or later with [`crate::HttpMcpServer::set_session_attribute`], and visible
+    /// to handlers and the tool policy as [`crate::RequestContext::session_attributes`].
+    pub attributes: HashMap<String, Value>,
+
+    closed: Arc<watch::Sender<bool>>,
+}
+
+impl Session {
+    pub fn new(
+        id: impl Into<String>,
+        protocol_version: ProtocolVersion,
+        client_info: Option<Implementation>,
+    ) -> Self {
+        let now = Instant::now();
+        Self {
+            id: id.into(),
+            protocol_version,
+            client_info,
+            state: SessionState::Initializing,
+            created_at: now,
+            last_activity: now,
+            attributes: HashMap::new(),
+            closed: Arc::new(watch::channel(false).0),
+        }
+    }
+
+    /// Generate a new, globally unique session ID
+    pub fn generate_id() -> String {
+        Uuid::new_v4().to_string()
+    }
+
+    /// Resolves once the session is closed
+    ///
+    /// Open SSE streams and in-flight requests wait on this to stop early.
+    pub async fn closed(&self) {
+        let mut rx = self.closed.subscribe();
+        let _ = rx.wait_for(|closed| *closed).await;
+    }
+
+    /// Whether the session has outlived either timeout
+    pub fn expiry(
+        &self,
+        idle_timeout: Option<Duration>,
+        max_lifetime: Option<Duration>,
+    ) -> Option<SessionCloseReason> {
+        if max_lifetime.is_some_and(|max| self.created_at.elapsed() >= max) {
+            Some(SessionCloseReason::MaxLifetime)
+        } else if idle_timeout.is_some_and(|idle| self.last_activity.elapsed() >= idle) {
+            Some(SessionCloseReason::IdleTimeout)
+        } else {
+            None
+        }
+    }
+
+    fn close(&mut self) {
+        self.state = SessionState::Closed;
+        self.closed.send_replace(true);
+    }
+}
+
+/// In-memory registry of active sessions
+#[derive(Debug, Default)]
+pub struct SessionStore {
+    sessions: RwLock<HashMap<String, Session>>,
+}
+
+impl SessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, session: Session) {
+        self.sessions
+            .write()
+            .unwrap()
+            .insert(session.id.clone(), session);
+    }
+
+    /// Record a successful `initialize` on a session, creating it if needed
+    ///
+    /// An existing session (such as one opened by a legacy SSE stream) keeps its
+    /// close signal so streams waiting on it stay attached.
+    pub fn initialize(
+        &self,
+        id: &str,
+        protocol_version: ProtocolVersion,
+        client_info: Option<Implementation>,
+    ) {
+        let mut sessions = self.sessions.write().unwrap();
+        match sessions.get_mut(id) {
+            Some(session) => {
+                session.protocol_version = protocol_version;
+                session.client_info = client_info;
+                session.state = SessionState::Initializing;
+                session.last_activity = Instant::now();
+            }
+            None => {
+                sessions.insert(
+                    id.to_string(),
+                    Session::new(id, protocol_version, client_info),
+                );
+            }
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<Session> {
+        self.sessions.read().unwrap().get(id).cloned()
+    }
+
+    /// Record activity on a session, returning the updated session
+    pub fn touch(&self, id: &str) -> Option<Session> {
+        let mut sessions = self.sessions.write().unwrap();
+        let session = sessions.get_mut(id)?;
+        session.last_activity = Instant::now();
+        Some(session.clone())
+    }
+
+    /// Set one of a session's attributes; returns false if the session does not exist
+    pub fn set_attribute(&self, id: &str, key: impl Into<String>, value: Value) -> bool {
+        let mut sessions = self.sessions.write().unwrap();
+        let Some(session) = sessions.get_mut(id) else {
+            return false;
+        };
+        session.attributes.insert(key.into(), value);
+        true
+    }
+
+    /// Move a session to a new lifecycle state, returning the updated session
+    pub fn set_state(&self, id: &str, state: SessionState) -> Option<Session> {
+        let mut sessions = self.sessions.write().unwrap();
+        let session = sessions.get_mut(id)?;
+        session.state = state;
+        Some(session.clone())
+    }
+
+    /// Remove a session and signal everything waiting on [`Session::closed`]
+    pub fn close(&self, id: &str) -> Option<Session> {
+        let mut session = self.sessions.write().unwrap().remove(id)?;
+        session.close();
+        Some(session)
+    }
+
+    /// IDs of all open sessions
+    pub fn ids(&self) -> Vec<String> {
+        self.sessions.read().unwrap().keys().cloned().collect()
+    }
+
+    /// IDs of sessions past either timeout, with the reason they expired
+    pub fn expired(
+        &self,
+        idle_timeout: Option<Duration>,
+        max_lifetime: Option<Duration>,
+    ) -> Vec<(String, SessionCloseReason)> {
+        self.sessions
+            .read()
+            .unwrap()
+            .values()
+            .filter_map(|s| {
+                s.expiry(idle_timeout, max_lifetime)
+                    .map(|reason| (s.id.clone(), reason))
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
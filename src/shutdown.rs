@@ -0,0 +1,185 @@
+use crate::server::HttpMcpServer;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// How far the server has got in shutting down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Running,
+    /// New requests are refused while in-flight ones finish
+    Draining,
+    /// Streams end and sessions close
+    Closed,
+}
+
+/// Shutdown progress and in-flight request count, shared by every transport
+#[derive(Debug)]
+pub(crate) struct ShutdownState {
+    phase: watch::Sender<Phase>,
+    in_flight: Arc<watch::Sender<usize>>,
+}
+
+impl Default for ShutdownState {
+    fn default() -> Self {
+        Self {
+            phase: watch::channel(Phase::Running).0,
+            in_flight: Arc::new(watch::channel(0).0),
+        }
+    }
+}
+
+impl ShutdownState {
+    pub(crate) fn is_running(&self) -> bool {
+        *self.phase.borrow() == Phase::Running
+    }
+
+    /// Start refusing requests; returns false if shutdown had already begun
+    pub(crate) fn begin_drain(&self) -> bool {
+        self.phase.send_if_modified(|phase| {
+            let started = *phase == Phase::Running;
+            if started {
+                *phase = Phase::Draining;
+            }
+            started
+        })
+    }
+
+    pub(crate) fn close(&self) {
+        self.phase.send_replace(Phase::Closed);
+    }
+
+    /// Resolves once open streams should send their final event and end
+    pub(crate) fn closed(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut rx = self.phase.subscribe();
+        async move {
+            let _ = rx.wait_for(|phase| *phase == Phase::Closed).await;
+        }
+    }
+
+    /// Count a request as in flight until the guard is dropped
+    pub(crate) fn track(&self) -> InFlight {
+        self.in_flight.send_modify(|n| *n += 1);
+        InFlight(self.in_flight.clone())
+    }
+
+    pub(crate) fn in_flight(&self) -> usize {
+        *self.in_flight.borrow()
+    }
+
+    /// Resolves once no request is in flight
+    pub(crate) async fn drained(&self) {
+        let mut rx = self.in_flight.subscribe();
+        let _ = rx.wait_for(|n| *n == 0).await;
+    }
+}
+
+/// Marks a request as in flight while alive
+pub(crate) struct InFlight(Arc<watch::Sender<usize>>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.send_modify(|n| *n -= 1);
+    }
+}
+
+type StopResult = Option<Result<(), Arc<io::Error>>>;
+
+/// Handle to a server started with [`HttpMcpServer::start`]
+///
+/// Clones control the same server, so one can wait on [`stopped`](Self::stopped)
+/// while another triggers [`shutdown`](Self::shutdown).
+#[derive(Clone)]
+pub struct ServerHandle {
+    server: Arc<HttpMcpServer>,
+    http: actix_web::dev::ServerHandle,
+    addrs: Vec<SocketAddr>,
+    stopped: watch::Receiver<StopResult>,
+}
+
+impl ServerHandle {
+    /// Drive the actix-web server on the current Tokio runtime
+    pub(crate) fn spawn(
+        server: Arc<HttpMcpServer>,
+        http: actix_web::dev::Server,
+        addrs: Vec<SocketAddr>,
+    ) -> Self {
+        let (tx, stopped) = watch::channel(None);
+        let handle = http.handle();
+        tokio::spawn(async move {
+            let result = http.await.map_err(Arc::new);
+            tx.send_replace(Some(result));
+        });
+        Self {
+            server,
+            http: handle,
+            addrs,
+            stopped,
+        }
+    }
+
+    pub fn server(&self) -> &Arc<HttpMcpServer> {
+        &self.server
+    }
+
+    /// Addresses the server is listening on, with the actual port when port 0 was bound
+    ///
+    /// Empty for a Unix domain socket.
+    pub fn addrs(&self) -> &[SocketAddr] {
+        &self.addrs
+    }
+
+    /// Stop gracefully, resolving once the server has stopped
+    ///
+    /// The listener stops accepting connections and the server is drained with
+    /// [`HttpMcpServer::shutdown`] using the configured
+    /// [`shutdown_timeout`](crate::HttpMcpServerBuilder::shutdown_timeout).
+    pub async fn shutdown(&self) -> io::Result<()> {
+        tracing::info!("Stopping MCP server");
+        // Stopping actix first would drop in-flight handlers, so drain before it
+        self.http.pause().await;
+        self.server.shutdown(self.server.shutdown_timeout).await;
+        self.http.stop(true).await;
+        self.stopped().await
+    }
+
+    /// Wait until the server stops, shutting it down gracefully if `signal` resolves first
+    pub(crate) async fn serve_until(self, signal: impl Future<Output = ()>) -> io::Result<()> {
+        tokio::select! {
+            result = self.stopped() => result,
+            _ = signal => self.shutdown().await,
+        }
+    }
+
+    /// Resolves once the server has stopped
+    pub async fn stopped(&self) -> io::Result<()> {
+        let mut stopped = self.stopped.clone();
+        let result = match stopped.wait_for(Option::is_some).await {
+            Ok(result) => result.clone(),
+            // The server task is gone, so the server is not running either
+            Err(_) => return Ok(()),
+        };
+        match result {
+            Some(Err(e)) => Err(io::Error::new(e.kind(), e.to_string())),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Resolves on Ctrl-C, or on SIGTERM on Unix
+pub(crate) async fn termination_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
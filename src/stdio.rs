@@ -0,0 +1,339 @@
+use crate::context::RequestContext;
+use crate::jsonrpc::JsonRpcMessage;
+use crate::server::HttpMcpServer;
+use crate::session::{Session, SessionCloseReason, SessionState};
+use crate::transport::dispatch_message;
+use crate::version::ProtocolVersion;
+use actix_web::http::header::HeaderMap;
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+/// Serve MCP over newline-delimited JSON-RPC on a pair of byte streams
+///
+/// Each line read from `input` is one message. Responses and notifications are
+/// written to `output` one per line; requests are handled concurrently, so
+/// responses may arrive out of order. The whole connection is a single session,
+/// which ends when `input` reaches end of file. Requests still running then are
+/// given the server's shutdown timeout to respond before they are cancelled.
+pub async fn serve<R, W>(server: Arc<HttpMcpServer>, input: R, output: W) -> std::io::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let mut session = Session::new(Session::generate_id(), ProtocolVersion::LATEST, None);
+    session.state = SessionState::Uninitialized;
+    let session_id = session.id.clone();
+    server.sessions.insert(session);
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    server
+        .session_streams
+        .write()
+        .unwrap()
+        .insert(session_id.clone(), tx.clone());
+    let mut writer = tokio::spawn(write_messages(rx, output));
+
+    let mut ctx = RequestContext::new(
+        HeaderMap::new(),
+        "STDIO".to_string(),
+        "stdio".to_string(),
+        None,
+    );
+    ctx.session_id = Some(session_id.clone());
+
+    let mut lines = input.lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        dispatch_message(&server, ctx.clone(), line.as_bytes(), &tx).await;
+    }
+
+    tracing::debug!("stdin closed, ending session {}", session_id);
+    server.session_streams.write().unwrap().remove(&session_id);
+    // The client can no longer answer, so handlers waiting on it fail instead of hanging
+    server.fail_pending_requests(&session_id);
+    drop(tx);
+    // In-flight requests keep the writer open until they have responded, or until
+    // ending the session cancels those still running after the shutdown timeout
+    let drained = tokio::time::timeout(server.shutdown_timeout, &mut writer).await;
+    server
+        .end_session(&session_id, SessionCloseReason::Terminated)
+        .await;
+    let written = match drained {
+        Ok(written) => written,
+        Err(_) => writer.await,
+    };
+    written.unwrap_or_else(|e| Err(std::io::Error::new(std::io::ErrorKind::Other, e)))
+}
+
+/// Write each message as one line of JSON until every sender is dropped
+async fn write_messages<W>(
+    mut rx: mpsc::UnboundedReceiver<JsonRpcMessage>,
+    mut output: W,
+) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    while let Some(message) = rx.recv().await {
+        let mut line = serde_json::to_vec(&message)?;
+        line.push(b'\n');
+        output.write_all(&line).await?;
+        output.flush().await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::ToolMeta;
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn test_serve_over_pipes() {
+        let server = Arc::new(
+            HttpMcpServer::builder()
+                .tool("echo", ToolMeta::new(), |_args, _ctx| async { Ok("hi") })
+                .build()
+                .unwrap(),
+        );
+        let input = [
+            json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": {},
+                    "clientInfo": {"name": "desktop", "version": "1.0"}
+                }
+            }),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "tools/list"}),
+        ]
+        .iter()
+        .map(|m| m.to_string() + "\n")
+        .collect::<String>()
+            + "not json\n";
+
+        let (client, server_end) = tokio::io::duplex(64 * 1024);
+        serve(server.clone(), input.as_bytes(), server_end)
+            .await
+            .unwrap();
+
+        let mut output = String::new();
+        let mut client = tokio::io::BufReader::new(client);
+        while client.read_line(&mut output).await.unwrap() > 0 {}
+        let messages: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        let response = |id: Value| messages.iter().find(|m| m["id"] == id).unwrap();
+
+        assert_eq!(messages.len(), 4);
+        // Requests before initialize are refused
+        assert!(response(json!(1))["error"].is_object());
+        assert_eq!(
+            response(json!(2))["result"]["protocolVersion"],
+            "2025-06-18"
+        );
+        assert_eq!(response(json!(3))["result"]["tools"][0]["name"], "echo");
+        assert_eq!(response(Value::Null)["error"]["code"], -32700);
+        assert!(server.sessions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_server_to_client_request() {
+        let server = Arc::new(HttpMcpServer::builder().build().unwrap());
+        let (mut client_in, server_in) = tokio::io::duplex(64 * 1024);
+        let (client_out, server_out) = tokio::io::duplex(64 * 1024);
+        let serving = tokio::spawn(serve(
+            server.clone(),
+            tokio::io::BufReader::new(server_in),
+            server_out,
+        ));
+        let mut client_out = tokio::io::BufReader::new(client_out).lines();
+
+        // Wait for the session to exist by pinging it
+        let ping = json!({"jsonrpc": "2.0", "id": "p", "method": "ping"});
+        client_in
+            .write_all(format!("{}\n", ping).as_bytes())
+            .await
+            .unwrap();
+        client_out.next_line().await.unwrap().unwrap();
+        let session_id = server
+            .session_streams
+            .read()
+            .unwrap()
+            .keys()
+            .next()
+            .cloned()
+            .unwrap();
+
+        assert!(server.notify(
+            &session_id,
+            "notifications/message",
+            json!({"level": "info"})
+        ));
+        let notification: Value =
+            serde_json::from_str(&client_out.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(notification["method"], "notifications/message");
+
+        let request = {
+            let server = server.clone();
+            let session_id = session_id.clone();
+            tokio::spawn(async move { server.send_request(&session_id, "roots/list", None).await })
+        };
+        let sent: Value =
+            serde_json::from_str(&client_out.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(sent["method"], "roots/list");
+        let reply = json!({"jsonrpc": "2.0", "id": sent["id"], "result": {"roots": []}});
+        client_in
+            .write_all(format!("{}\n", reply).as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(request.await.unwrap().unwrap(), json!({"roots": []}));
+
+        // Requests still waiting when the session ends fail instead of hanging
+        let pending = {
+            let server = server.clone();
+            let session_id = session_id.clone();
+            tokio::spawn(async move { server.send_request(&session_id, "roots/list", None).await })
+        };
+        client_out.next_line().await.unwrap().unwrap();
+        drop(client_in);
+        serving.await.unwrap().unwrap();
+        assert!(matches!(
+            pending.await.unwrap(),
+            Err(crate::McpError::SessionNotFound(_))
+        ));
+        assert!(server
+            .send_request(&session_id, "ping", None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_eof_fails_requests_awaiting_the_client() {
+        let handle = Arc::new(std::sync::OnceLock::<std::sync::Weak<HttpMcpServer>>::new());
+        let server = Arc::new(
+            HttpMcpServer::builder()
+                .tool("ask", ToolMeta::new(), {
+                    let handle = handle.clone();
+                    move |_args, ctx: RequestContext| {
+                        let server = handle.get().and_then(|server| server.upgrade());
+                        async move {
+                            let server = server.unwrap();
+                            let session_id = ctx.session_id.unwrap_or_default();
+                            server.send_request(&session_id, "roots/list", None).await?;
+                            Ok("answered")
+                        }
+                    }
+                })
+                .build()
+                .unwrap(),
+        );
+        handle.set(Arc::downgrade(&server)).unwrap();
+
+        let (mut client_in, server_in) = tokio::io::duplex(64 * 1024);
+        let (client_out, server_out) = tokio::io::duplex(64 * 1024);
+        let serving = tokio::spawn(serve(
+            server.clone(),
+            tokio::io::BufReader::new(server_in),
+            server_out,
+        ));
+        let mut client_out = tokio::io::BufReader::new(client_out).lines();
+
+        let input = [
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": {},
+                    "clientInfo": {"name": "desktop", "version": "1.0"}
+                }
+            }),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "ask"}}),
+        ]
+        .iter()
+        .map(|m| m.to_string() + "\n")
+        .collect::<String>();
+        client_in.write_all(input.as_bytes()).await.unwrap();
+        client_out.next_line().await.unwrap().unwrap();
+        let sent: Value =
+            serde_json::from_str(&client_out.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(sent["method"], "roots/list");
+
+        // Closing stdin without answering must not leave the server waiting
+        drop(client_in);
+        tokio::time::timeout(std::time::Duration::from_secs(5), serving)
+            .await
+            .expect("serve hung after stdin closed")
+            .unwrap()
+            .unwrap();
+
+        // The tool still answers its own caller, with the failure
+        let response: Value =
+            serde_json::from_str(&client_out.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(response["id"], 2);
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Session not found"));
+        assert!(server.sessions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_client_response_timeout() {
+        let server = Arc::new(
+            HttpMcpServer::builder()
+                .client_response_timeout(std::time::Duration::from_millis(50))
+                .build()
+                .unwrap(),
+        );
+        let (mut client_in, server_in) = tokio::io::duplex(64 * 1024);
+        let (client_out, server_out) = tokio::io::duplex(64 * 1024);
+        let serving = tokio::spawn(serve(
+            server.clone(),
+            tokio::io::BufReader::new(server_in),
+            server_out,
+        ));
+        let mut client_out = tokio::io::BufReader::new(client_out).lines();
+
+        let ping = json!({"jsonrpc": "2.0", "id": "p", "method": "ping"});
+        client_in
+            .write_all(format!("{}\n", ping).as_bytes())
+            .await
+            .unwrap();
+        client_out.next_line().await.unwrap().unwrap();
+        let session_id = server
+            .session_streams
+            .read()
+            .unwrap()
+            .keys()
+            .next()
+            .cloned()
+            .unwrap();
+
+        // The client never answers, so the request is cancelled and forgotten
+        let result = server.send_request(&session_id, "roots/list", None).await;
+        assert!(matches!(result, Err(crate::McpError::ClientTimeout(ref m)) if m == "roots/list"));
+        let sent: Value =
+            serde_json::from_str(&client_out.next_line().await.unwrap().unwrap()).unwrap();
+        let cancelled: Value =
+            serde_json::from_str(&client_out.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(cancelled["method"], "notifications/cancelled");
+        assert_eq!(cancelled["params"]["requestId"], sent["id"]);
+        assert!(server.pending_requests.lock().unwrap().is_empty());
+
+        drop(client_in);
+        serving.await.unwrap().unwrap();
+    }
+}
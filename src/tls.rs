@@ -0,0 +1,369 @@
+//! HTTPS with rustls, optionally verifying client certificates (mTLS)
+//!
+//! Enabled with the `tls` cargo feature. Pass a [`TlsConfig`] to
+//! [`HttpMcpServerBuilder::tls`](crate::HttpMcpServerBuilder::tls) and start the
+//! server as usual. The certificate and key are re-read when the files change,
+//! so renewed certificates are picked up without a restart.
+//!
+//! ```rust,no_run
+//! use httpmcp_rust::{HttpMcpServer, TlsConfig};
+//!
+//! # async fn example() -> std::io::Result<()> {
+//! let server = HttpMcpServer::builder()
+//!     .tls(TlsConfig::new("cert.pem", "key.pem").client_ca("clients-ca.pem"))
+//!     .build()
+//!     .expect("Failed to build server");
+//! server.run("0.0.0.0:8443").await
+//! # }
+//! ```
+
+use crate::context::PeerCertificate;
+use actix_tls::accept::rustls_0_23::TlsStream;
+use actix_web::dev::Extensions;
+use actix_web::rt::net::TcpStream;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier};
+use rustls::sign::CertifiedKey;
+use rustls::{RootCertStore, ServerConfig};
+use std::any::Any;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+/// Default interval for checking the certificate and key files for changes
+pub const DEFAULT_RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+/// TLS settings for the HTTP listener
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    client_ca_path: Option<PathBuf>,
+    client_auth_optional: bool,
+    reload_interval: Option<Duration>,
+}
+
+impl TlsConfig {
+    /// Serve the PEM certificate chain at `cert` with the PEM private key at `key`
+    pub fn new(cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        Self {
+            cert_path: cert.into(),
+            key_path: key.into(),
+            client_ca_path: None,
+            client_auth_optional: false,
+            reload_interval: Some(DEFAULT_RELOAD_INTERVAL),
+        }
+    }
+
+    /// Require client certificates signed by one of the PEM CA certificates at `path`
+    ///
+    /// The verified certificate is available to handlers as
+    /// [`RequestContext::peer_certificate`](crate::RequestContext::peer_certificate).
+    pub fn client_ca(mut self, path: impl Into<PathBuf>) -> Self {
+        self.client_ca_path = Some(path.into());
+        self
+    }
+
+    /// Also accept clients without a certificate (default: false)
+    ///
+    /// Certificates that are presented must still verify against the client CA.
+    pub fn client_auth_optional(mut self, optional: bool) -> Self {
+        self.client_auth_optional = optional;
+        self
+    }
+
+    /// How often to check the certificate and key files for changes (default: 30s)
+    pub fn reload_interval(mut self, interval: Duration) -> Self {
+        self.reload_interval = Some(interval);
+        self
+    }
+
+    /// Load the certificate and key once and never check them again
+    pub fn disable_reload(mut self) -> Self {
+        self.reload_interval = None;
+        self
+    }
+
+    /// Build the rustls configuration, watching the certificate files if enabled
+    ///
+    /// Must be called within a Tokio runtime when reloading is enabled.
+    pub(crate) fn server_config(&self) -> io::Result<ServerConfig> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let resolver = Arc::new(CertResolver::load(
+            &self.cert_path,
+            &self.key_path,
+            provider.clone(),
+        )?);
+        if let Some(interval) = self.reload_interval {
+            resolver.watch(interval);
+        }
+
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(invalid_data)?;
+        let builder = match &self.client_ca_path {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in read_certs(path)? {
+                    roots.add(cert).map_err(invalid_data)?;
+                }
+                let verifier = WebPkiClientVerifier::builder_with_provider(roots.into(), provider);
+                let verifier = if self.client_auth_optional {
+                    verifier.allow_unauthenticated()
+                } else {
+                    verifier
+                };
+                builder.with_client_cert_verifier(verifier.build().map_err(invalid_data)?)
+            }
+            None => builder.with_no_client_auth(),
+        };
+        Ok(builder.with_cert_resolver(resolver))
+    }
+}
+
+/// Serves the current certificate, swapping it when the files on disk change
+#[derive(Debug)]
+struct CertResolver {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    provider: Arc<CryptoProvider>,
+    current: RwLock<Arc<CertifiedKey>>,
+    modified: RwLock<Option<SystemTime>>,
+}
+
+impl CertResolver {
+    fn load(cert_path: &Path, key_path: &Path, provider: Arc<CryptoProvider>) -> io::Result<Self> {
+        let key = load_certified_key(cert_path, key_path, &provider)?;
+        Ok(Self {
+            cert_path: cert_path.to_path_buf(),
+            key_path: key_path.to_path_buf(),
+            provider,
+            current: RwLock::new(Arc::new(key)),
+            modified: RwLock::new(last_modified(cert_path, key_path)),
+        })
+    }
+
+    /// Reload the certificate if either file changed; returns whether it was replaced
+    ///
+    /// A file that fails to load keeps the previous certificate in service.
+    fn reload_if_changed(&self) -> bool {
+        let modified = last_modified(&self.cert_path, &self.key_path);
+        if modified == *self.modified.read().unwrap() {
+            return false;
+        }
+        match load_certified_key(&self.cert_path, &self.key_path, &self.provider) {
+            Ok(key) => {
+                *self.current.write().unwrap() = Arc::new(key);
+                *self.modified.write().unwrap() = modified;
+                tracing::info!("Reloaded TLS certificate from {}", self.cert_path.display());
+                true
+            }
+            Err(e) => {
+                tracing::warn!("Keeping previous TLS certificate: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Start the background task that checks the files every `interval`
+    ///
+    /// The task holds a weak reference and stops once the listener is dropped.
+    fn watch(self: &Arc<Self>, interval: Duration) {
+        let resolver = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let Some(resolver) = resolver.upgrade() else {
+                    break;
+                };
+                resolver.reload_if_changed();
+            }
+        });
+    }
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap().clone())
+    }
+}
+
+/// Record the client certificate of a TLS connection for its requests
+pub(crate) fn on_connect(conn: &dyn Any, data: &mut Extensions) {
+    let Some(stream) = conn.downcast_ref::<TlsStream<TcpStream>>() else {
+        return;
+    };
+    let (_, session) = stream.get_ref();
+    let Some(cert) = session.peer_certificates().and_then(|certs| certs.first()) else {
+        return;
+    };
+    match peer_certificate(cert.as_ref()) {
+        Some(cert) => {
+            data.insert(cert);
+        }
+        None => tracing::warn!("Could not parse the verified client certificate"),
+    }
+}
+
+fn peer_certificate(der: &[u8]) -> Option<PeerCertificate> {
+    let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+    let common_name = cert
+        .subject()
+        .iter_common_name()
+        .next()
+        .and_then(|cn| cn.as_str().ok())
+        .map(|cn| cn.to_string());
+    Some(PeerCertificate {
+        subject: cert.subject().to_string(),
+        common_name,
+        der: der.to_vec(),
+    })
+}
+
+fn load_certified_key(
+    cert_path: &Path,
+    key_path: &Path,
+    provider: &CryptoProvider,
+) -> io::Result<CertifiedKey> {
+    let certs = read_certs(cert_path)?;
+    let key = read_key(key_path)?;
+    let key = provider
+        .key_provider
+        .load_private_key(key)
+        .map_err(invalid_data)?;
+    // Catches a renewed certificate read before its new key was written
+    let certified = CertifiedKey::new(certs, key);
+    certified.keys_match().map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} does not match {}: {}",
+                key_path.display(),
+                cert_path.display(),
+                e
+            ),
+        )
+    })?;
+    Ok(certified)
+}
+
+fn read_certs(path: &Path) -> io::Result<Vec<CertificateDer<'static>>> {
+    let mut reader = BufReader::new(File::open(path).map_err(|e| with_path(e, path))?);
+    let certs = rustls_pemfile::certs(&mut reader).collect::<io::Result<Vec<_>>>()?;
+    if certs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("no certificates found in {}", path.display()),
+        ));
+    }
+    Ok(certs)
+}
+
+fn read_key(path: &Path) -> io::Result<PrivateKeyDer<'static>> {
+    let mut reader = BufReader::new(File::open(path).map_err(|e| with_path(e, path))?);
+    rustls_pemfile::private_key(&mut reader)?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("no private key found in {}", path.display()),
+        )
+    })
+}
+
+fn last_modified(cert_path: &Path, key_path: &Path) -> Option<SystemTime> {
+    let cert = std::fs::metadata(cert_path)
+        .and_then(|m| m.modified())
+        .ok()?;
+    let key = std::fs::metadata(key_path)
+        .and_then(|m| m.modified())
+        .ok()?;
+    Some(cert.max(key))
+}
+
+fn with_path(e: io::Error, path: &Path) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {}", path.display(), e))
+}
+
+fn invalid_data(e: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/tls")
+            .join(name)
+    }
+
+    #[test]
+    fn test_peer_certificate_subject() {
+        let der = read_certs(&fixture("client.pem")).unwrap().remove(0);
+        let cert = peer_certificate(der.as_ref()).unwrap();
+        assert_eq!(cert.subject, "O=Example, CN=agent-1");
+        assert_eq!(cert.common_name.as_deref(), Some("agent-1"));
+        assert_eq!(cert.der, der.as_ref());
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let err = CertResolver::load(
+            &fixture("missing.pem"),
+            &fixture("server.key"),
+            provider.clone(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("missing.pem"));
+        // A certificate file holds no private key
+        let err = CertResolver::load(
+            &fixture("server.pem"),
+            &fixture("server.pem"),
+            provider.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        // A key from another pair
+        let err = CertResolver::load(&fixture("server.pem"), &fixture("client.key"), provider)
+            .unwrap_err();
+        assert!(err.to_string().contains("does not match"));
+    }
+
+    #[test]
+    fn test_reload_on_change() {
+        let dir = std::env::temp_dir().join(format!("httpmcp-tls-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (cert, key) = (dir.join("cert.pem"), dir.join("key.pem"));
+        std::fs::copy(fixture("server.pem"), &cert).unwrap();
+        std::fs::copy(fixture("server.key"), &key).unwrap();
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let resolver = CertResolver::load(&cert, &key, provider).unwrap();
+        let served = || resolver.current.read().unwrap().cert[0].clone();
+        let original = served();
+        assert!(!resolver.reload_if_changed());
+
+        // A half-written pair keeps the old certificate in service
+        std::fs::write(&key, "").unwrap();
+        assert!(!resolver.reload_if_changed());
+        assert_eq!(served(), original);
+
+        // So does a new certificate whose key has not been written yet
+        std::fs::copy(fixture("client.pem"), &cert).unwrap();
+        std::fs::copy(fixture("server.key"), &key).unwrap();
+        assert!(!resolver.reload_if_changed());
+        assert_eq!(served(), original);
+
+        std::fs::copy(fixture("client.key"), &key).unwrap();
+        assert!(resolver.reload_if_changed());
+        assert_ne!(served(), original);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,448 @@
+//! Serve the MCP endpoint as a [`tower_service::Service`] for hyper, axum and
+//! other tower-based stacks
+//!
+//! Enabled with the `tower` cargo feature. The service answers POST, GET and
+//! DELETE like `/mcp` does under actix-web, with the same sessions, SSE
+//! streaming, authentication, content negotiation and `Origin`/`Host` checks.
+//! CORS is left to the surrounding stack, for example tower-http's `CorsLayer`.
+//!
+//! In axum, mount it with `Router::new().route_service("/mcp", mcp)`. The
+//! service can also be called directly:
+//!
+//! ```rust,no_run
+//! use http_body_util::Full;
+//! use httpmcp_rust::tower::McpService;
+//! use httpmcp_rust::HttpMcpServer;
+//! use tower_service::Service;
+//!
+//! # async fn example() -> httpmcp_rust::Result<()> {
+//! let mut mcp = McpService::new(HttpMcpServer::builder().name("my-server").build()?);
+//!
+//! let body = r#"{"jsonrpc": "2.0", "id": 1, "method": "ping"}"#;
+//! let request = http::Request::post("/mcp")
+//!     .header("content-type", "application/json")
+//!     .header("accept", "application/json, text/event-stream")
+//!     .body(Full::new(bytes::Bytes::from(body)))
+//!     .unwrap();
+//! let response = mcp.call(request).await.unwrap();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::context::{PeerCertificate, RequestContext};
+use crate::error::McpError;
+use crate::jsonrpc::{JsonRpcRequest, JsonRpcResponse, RequestId};
+use crate::middleware::check_headers;
+use crate::server::HttpMcpServer;
+use crate::session::SESSION_ID_HEADER;
+use crate::transport::{
+    authenticate, complete_client_request, parse_batch, parse_response, process_batch,
+    process_post, session_events, sse_message, terminate_session, PostReply,
+};
+use actix_web::ResponseError;
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::stream::{BoxStream, StreamExt};
+use http::{header, Method, Request, Response, StatusCode};
+use http_body::{Body, Frame};
+use http_body_util::combinators::UnsyncBoxBody;
+use http_body_util::{BodyExt, Empty, Full, LengthLimitError, Limited, StreamBody};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Response body of [`McpService`]
+pub type McpBody = UnsyncBoxBody<Bytes, Infallible>;
+
+/// The MCP endpoint as a tower service
+///
+/// Cloning is cheap; every clone serves the same server and sessions. The
+/// client address is read from a [`SocketAddr`] request extension when present,
+/// and a TLS client certificate from a [`PeerCertificate`] extension.
+#[derive(Clone)]
+pub struct McpService {
+    server: Arc<HttpMcpServer>,
+}
+
+impl McpService {
+    /// Wrap a server, starting its session reaper when called inside a Tokio runtime
+    pub fn new(server: impl Into<Arc<HttpMcpServer>>) -> Self {
+        let server = server.into();
+        if tokio::runtime::Handle::try_current().is_ok() {
+            HttpMcpServer::spawn_session_reaper(&server);
+        }
+        Self { server }
+    }
+
+    pub fn server(&self) -> &Arc<HttpMcpServer> {
+        &self.server
+    }
+}
+
+impl<B> tower_service::Service<Request<B>> for McpService
+where
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Response = Response<McpBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let server = self.server.clone();
+        Box::pin(async move { Ok(handle(server, req).await) })
+    }
+}
+
+async fn handle<B>(server: Arc<HttpMcpServer>, req: Request<B>) -> Response<McpBody>
+where
+    B: Body + Send + 'static,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let (parts, body) = req.into_parts();
+    let headers = to_actix_headers(&parts.headers);
+    let method =
+        actix_web::http::Method::from_bytes(parts.method.as_str().as_bytes()).unwrap_or_default();
+
+    let checked = server
+        .rebinding_guard
+        .check(&headers, parts.uri.authority().map(|a| a.as_str()))
+        .and_then(|_| check_headers(&method, &headers, server.lenient_content_negotiation));
+    if let Err(e) = checked {
+        tracing::debug!("Rejecting {} request: {}", method, e);
+        return error_response(&e, None);
+    }
+
+    let accept_sse = parts
+        .headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|s| s.contains("text/event-stream"));
+    let mut ctx = RequestContext::new(
+        headers,
+        parts.method.to_string(),
+        parts.uri.path().to_string(),
+        parts.extensions.get::<SocketAddr>().copied(),
+    );
+    ctx.peer_certificate = parts.extensions.get::<PeerCertificate>().cloned();
+    if let Err(e) = authenticate(&ctx, &server).await {
+        return error_response(&e, None);
+    }
+
+    match parts.method {
+        Method::POST => handle_post(server, ctx, body, accept_sse).await,
+        Method::GET => match session_events(&ctx, &server).await {
+            Ok(events) => sse_response(events),
+            Err(e) => error_response(&e, None),
+        },
+        Method::DELETE => match terminate_session(&ctx, &server).await {
+            Ok(()) => empty_response(StatusCode::NO_CONTENT),
+            Err(e) => error_response(&e, None),
+        },
+        _ => {
+            let mut resp = empty_response(StatusCode::METHOD_NOT_ALLOWED);
+            resp.headers_mut().insert(
+                header::ALLOW,
+                header::HeaderValue::from_static("GET, POST, DELETE"),
+            );
+            resp
+        }
+    }
+}
+
+async fn handle_post<B>(
+    server: Arc<HttpMcpServer>,
+    ctx: RequestContext,
+    body: B,
+    accept_sse: bool,
+) -> Response<McpBody>
+where
+    B: Body + Send + 'static,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let limit = server.config.mcp_payload_limit;
+    let body = match Limited::new(body, limit).collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) if e.is::<LengthLimitError>() => {
+            return error_response(&McpError::PayloadTooLarge(limit), None)
+        }
+        Err(e) => {
+            let e = McpError::InvalidRequest(format!("Failed to read request body: {}", e));
+            return error_response(&e, None);
+        }
+    };
+    if let Some(messages) = parse_batch(&body) {
+        return match process_batch(ctx, messages, &server).await {
+            Ok(responses) if responses.is_empty() => empty_response(StatusCode::ACCEPTED),
+            Ok(responses) => json_body(StatusCode::OK, &responses),
+            Err(e) => error_response(&e, None),
+        };
+    }
+    if let Some(response) = parse_response(&body) {
+        return match complete_client_request(&ctx, &server, response).await {
+            Ok(()) => empty_response(StatusCode::ACCEPTED),
+            Err(e) => error_response(&e, None),
+        };
+    }
+    let body = match JsonRpcRequest::parse(&body) {
+        Ok(body) => body,
+        Err((error, id)) => return json_response(&server, &JsonRpcResponse::error(error, id)),
+    };
+
+    let id = body.id.clone();
+    match process_post(ctx, body, accept_sse, server.clone()).await {
+        Ok(PostReply::Accepted) => empty_response(StatusCode::ACCEPTED),
+        Ok(PostReply::Json(response, session_id)) => {
+            let mut resp = json_response(&server, &response);
+            if let Some(value) = session_id.and_then(|id| header::HeaderValue::from_str(&id).ok()) {
+                resp.headers_mut().insert(SESSION_ID_HEADER, value);
+            }
+            resp
+        }
+        Ok(PostReply::Stream(messages)) => sse_response(
+            messages
+                .filter_map(|message| futures::future::ready(sse_message(&message).ok()))
+                .boxed(),
+        ),
+        Err(e) => error_response(&e, id),
+    }
+}
+
+/// Copy request headers into the actix-web header map used by [`RequestContext`]
+fn to_actix_headers(headers: &http::HeaderMap) -> actix_web::http::header::HeaderMap {
+    use actix_web::http::header::{HeaderName, HeaderValue};
+
+    let mut map = actix_web::http::header::HeaderMap::new();
+    for (name, value) in headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_str().as_bytes()),
+            HeaderValue::from_bytes(value.as_bytes()),
+        ) {
+            map.append(name, value);
+        }
+    }
+    map
+}
+
+fn status(code: actix_web::http::StatusCode) -> StatusCode {
+    StatusCode::from_u16(code.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn empty_response(status: StatusCode) -> Response<McpBody> {
+    let mut resp = Response::new(Empty::new().boxed_unsync());
+    *resp.status_mut() = status;
+    resp
+}
+
+/// JSON response for a JSON-RPC message, with the status chosen by the server's error policy
+fn json_response(server: &HttpMcpServer, response: &JsonRpcResponse) -> Response<McpBody> {
+    let code = match &response.error {
+        Some(error) => status(server.error_status.status_for(error)),
+        None => StatusCode::OK,
+    };
+    json_body(code, response)
+}
+
+/// Transport-level failure with the error's own status and a JSON-RPC error body
+fn error_response(e: &McpError, id: Option<RequestId>) -> Response<McpBody> {
+    json_body(
+        status(e.status_code()),
+        &JsonRpcResponse::error(e.to_jsonrpc_error(), id),
+    )
+}
+
+fn json_body(status: StatusCode, response: &impl serde::Serialize) -> Response<McpBody> {
+    let body = serde_json::to_vec(response).unwrap_or_default();
+    let mut resp = Response::new(Full::new(Bytes::from(body)).boxed_unsync());
+    *resp.status_mut() = status;
+    resp.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+    resp
+}
+
+fn sse_response(events: BoxStream<'static, Bytes>) -> Response<McpBody> {
+    let frames = events.map(|event| Ok::<_, Infallible>(Frame::data(event)));
+    let mut resp = Response::new(StreamBody::new(frames).boxed_unsync());
+    let headers = resp.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("text/event-stream"),
+    );
+    headers.insert(
+        header::CACHE_CONTROL,
+        header::HeaderValue::from_static("no-cache"),
+    );
+    resp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::ToolMeta;
+    use serde_json::{json, Value};
+    use tower_service::Service;
+
+    fn request(method: Method, session_id: Option<&str>, body: Value) -> Request<Full<Bytes>> {
+        let mut builder = Request::builder()
+            .method(method)
+            .uri("/mcp")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::ACCEPT, "application/json, text/event-stream");
+        if let Some(id) = session_id {
+            builder = builder.header(SESSION_ID_HEADER, id);
+        }
+        builder
+            .body(Full::new(Bytes::from(body.to_string())))
+            .unwrap()
+    }
+
+    async fn body_text(resp: Response<McpBody>) -> String {
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_service_session_flow() {
+        let server = HttpMcpServer::builder()
+            .tool("echo", ToolMeta::new(), |_args, _ctx| async { Ok("hi") })
+            .max_payload_size(1024)
+            .build()
+            .unwrap();
+        let mut service = McpService::new(server);
+
+        let resp = service
+            .call(request(
+                Method::POST,
+                None,
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "initialize",
+                    "params": {
+                        "protocolVersion": "2025-06-18",
+                        "capabilities": {},
+                        "clientInfo": {"name": "test", "version": "1.0"}
+                    }
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let session_id = resp.headers()[SESSION_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body: Value = serde_json::from_str(&body_text(resp).await).unwrap();
+        assert_eq!(body["result"]["protocolVersion"], "2025-06-18");
+
+        let resp = service
+            .call(request(
+                Method::POST,
+                Some(&session_id),
+                json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+
+        // Requests accepting SSE are answered with a stream
+        let resp = service
+            .call(request(
+                Method::POST,
+                Some(&session_id),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], "text/event-stream");
+        let events = body_text(resp).await;
+        assert!(events.contains(r#""name":"echo""#));
+
+        let resp = service
+            .call(request(
+                Method::POST,
+                Some(&session_id),
+                json!({"jsonrpc": "2.0", "id": 3, "method": "ping", "params": {"pad": "x".repeat(2048)}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let resp = service
+            .call(request(Method::PUT, None, json!({})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let resp = service
+            .call(request(Method::DELETE, Some(&session_id), json!({})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        let resp = service
+            .call(request(
+                Method::POST,
+                Some(&session_id),
+                json!({"jsonrpc": "2.0", "id": 4, "method": "tools/list"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body: Value = serde_json::from_str(&body_text(resp).await).unwrap();
+        assert_eq!(body["id"], 4);
+    }
+
+    #[tokio::test]
+    async fn test_service_rejects_bad_requests() {
+        let server = HttpMcpServer::builder()
+            .allowed_hosts(&["mcp.example.com"])
+            .build()
+            .unwrap();
+        let mut service = McpService::new(server);
+
+        let mut req = request(
+            Method::POST,
+            None,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}),
+        );
+        req.headers_mut()
+            .insert(header::HOST, header::HeaderValue::from_static("evil.test"));
+        let resp = service.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let mut req = request(
+            Method::POST,
+            None,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}),
+        );
+        req.headers_mut().insert(
+            header::HOST,
+            header::HeaderValue::from_static("mcp.example.com"),
+        );
+        req.headers_mut().insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static("text/plain"),
+        );
+        let resp = service.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let mut req = request(Method::POST, None, json!({}));
+        *req.body_mut() = Full::new(Bytes::from_static(b"{not json"));
+        req.headers_mut().insert(
+            header::HOST,
+            header::HeaderValue::from_static("mcp.example.com"),
+        );
+        let resp = service.call(req).await.unwrap();
+        let body: Value = serde_json::from_str(&body_text(resp).await).unwrap();
+        assert_eq!(body["error"]["code"], -32700);
+    }
+}
@@ -1,37 +1,139 @@
-use crate::context::RequestContext;
+use crate::context::RequestNotifier;
+use crate::context::{PeerCertificate, RequestContext};
 use crate::error::{McpError, Result};
+use crate::handler_types::IntoToolResult;
 use crate::handlers::lifecycle::{handle_initialize, handle_ping};
-use crate::jsonrpc::{JsonRpcRequest, JsonRpcResponse};
+use crate::handlers::{ResourceProvider, ToolProvider};
+use crate::jsonrpc::{JsonRpcError, JsonRpcMessage, JsonRpcRequest, JsonRpcResponse};
+use crate::middleware::{apply_cors, compress_responses, validate_origin, validate_request};
 use crate::protocol::*;
 use crate::server::HttpMcpServer;
+use crate::session::{Session, SessionCloseReason, SessionState, SESSION_ID_HEADER};
+use crate::sse::SseEvent;
+use crate::version::{adapt_result, ProtocolVersion};
 use actix_multipart::Multipart;
+use actix_web::error::PayloadError;
+use actix_web::middleware::from_fn;
 use actix_web::{
-    get, post,
+    http::header,
+    mime,
     web::{self, Data},
-    HttpRequest, HttpResponse, Responder,
+    HttpMessage, HttpRequest, HttpResponse, Responder, ResponseError, Route,
 };
-use actix_web_lab::sse;
+use actix_ws::AggregatedMessage;
+use bytes::{Bytes, BytesMut};
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
 use serde_json::Value;
 use std::sync::Arc;
 
 /// Configure actix-web application
+///
+/// Serves the MCP endpoint at `/mcp`. Use [`configure`] to mount it under
+/// another path in an existing application; the same notes on session
+/// timeouts and `Host`/`Origin` defaults apply.
 pub fn create_app(cfg: &mut web::ServiceConfig, server: Arc<HttpMcpServer>) {
-    if server.enable_cors {
-        cfg.default_service(web::to(|| async {
-            HttpResponse::Ok()
-                .insert_header(("Access-Control-Allow-Origin", "*"))
-                .insert_header((
-                    "Access-Control-Allow-Methods",
-                    "GET, POST, PUT, DELETE, PATCH, OPTIONS",
-                ))
-                .insert_header(("Access-Control-Allow-Headers", "*"))
-                .finish()
-        }));
-    }
-
-    cfg.app_data(Data::new(server.clone()))
-        .service(handle_post)
-        .service(handle_get);
+    cfg.app_data(Data::new(server.clone()));
+    mount(cfg, server, "");
+}
+
+/// Mount a server's routes into an existing actix-web application under `prefix`
+///
+/// Every route the server serves moves with it: `{prefix}/mcp`, and when enabled
+/// `{prefix}/mcp/ws`, `{prefix}/sse`, `{prefix}/messages` and custom endpoints.
+/// Each route carries its own server, so differently configured servers can be
+/// mounted side by side. Session timeouts are enforced by a background task
+/// started the first time the server is mounted.
+///
+/// The loopback `Host` and `Origin` defaults that [`HttpMcpServer::run`] applies
+/// on loopback addresses are not applied here, since the listener is not known.
+/// Set [`allowed_hosts`](crate::HttpMcpServerBuilder::allowed_hosts) and
+/// [`allowed_origins`](crate::HttpMcpServerBuilder::allowed_origins) explicitly
+/// to guard a mounted server against DNS rebinding.
+///
+/// ```rust,no_run
+/// use actix_web::{web, App, HttpResponse, HttpServer};
+/// use httpmcp_rust::transport::configure;
+/// use httpmcp_rust::HttpMcpServer;
+/// use std::sync::Arc;
+///
+/// # async fn example() -> std::io::Result<()> {
+/// let public = Arc::new(HttpMcpServer::builder().name("public").build().unwrap());
+/// let admin = Arc::new(HttpMcpServer::builder().name("admin").build().unwrap());
+///
+/// HttpServer::new(move || {
+///     App::new()
+///         .route("/", web::get().to(HttpResponse::Ok))
+///         .configure(configure(public.clone(), "/public"))
+///         .configure(configure(admin.clone(), "/admin"))
+/// })
+/// .bind("127.0.0.1:8080")?
+/// .run()
+/// .await
+/// # }
+/// ```
+pub fn configure(server: Arc<HttpMcpServer>, prefix: &str) -> impl FnOnce(&mut web::ServiceConfig) {
+    let prefix = match prefix.trim_end_matches('/') {
+        "" => String::new(),
+        p if p.starts_with('/') => p.to_string(),
+        p => format!("/{}", p),
+    };
+    move |cfg| mount(cfg, server, &prefix)
+}
+
+fn mount(cfg: &mut web::ServiceConfig, server: Arc<HttpMcpServer>, prefix: &str) {
+    HttpMcpServer::spawn_session_reaper(&server);
+    let data = Data::new(server.clone());
+    let path = |route: &str| format!("{}{}", prefix, route);
+
+    cfg.service(
+        web::resource(path("/mcp"))
+            .app_data(data.clone())
+            .wrap(from_fn(validate_request))
+            .wrap(from_fn(apply_cors))
+            .wrap(from_fn(validate_origin))
+            .wrap(from_fn(compress_responses))
+            .route(web::post().to(handle_post))
+            .route(web::get().to(handle_get))
+            .route(web::delete().to(handle_delete)),
+    );
+
+    if server.legacy_sse {
+        cfg.service(
+            web::resource(path("/sse"))
+                .app_data(data.clone())
+                .wrap(from_fn(apply_cors))
+                .wrap(from_fn(validate_origin))
+                .wrap(from_fn(compress_responses))
+                .route(web::get().to(handle_legacy_sse)),
+        )
+        .service(
+            web::resource(path("/messages"))
+                .app_data(data.clone())
+                .wrap(from_fn(apply_cors))
+                .wrap(from_fn(validate_origin))
+                .wrap(from_fn(compress_responses))
+                .route(web::post().to(handle_legacy_message)),
+        );
+    }
+
+    if server.websocket {
+        cfg.service(
+            web::resource(path("/mcp/ws"))
+                .app_data(data.clone())
+                .wrap(from_fn(apply_cors))
+                .wrap(from_fn(validate_origin))
+                .route(web::get().to(handle_websocket)),
+        );
+    }
+
+    // Routes for custom endpoints, grouped by path so each path is one resource
+    let mut resources: Vec<(String, Vec<Route>)> = Vec::new();
+    let mut add_route =
+        |path: &str, route: Route| match resources.iter_mut().find(|(p, _)| p == path) {
+            Some((_, routes)) => routes.push(route),
+            None => resources.push((path.to_string(), vec![route])),
+        };
 
     // Register custom endpoints dynamically
     for endpoint in &server.endpoints {
@@ -40,14 +142,22 @@ pub fn create_app(cfg: &mut web::ServiceConfig, server: Arc<HttpMcpServer>) {
         let handler = endpoint.handler.clone();
         let server_clone = server.clone();
 
-        cfg.route(
+        add_route(
             &route,
             web::method(parse_http_method(&method)).to(
-                move |req: HttpRequest, body: Option<web::Json<Value>>| {
+                move |req: HttpRequest, payload: web::Payload| {
                     let handler = handler.clone();
                     let server_clone = server_clone.clone();
                     async move {
                         let ctx = create_request_context(&req);
+                        let limit = server_clone.config.endpoint_payload_limit;
+                        let body = match read_payload(&req, payload, limit).await {
+                            Ok(body) => body,
+                            Err(e) => {
+                                return Ok(HttpResponse::build(e.status_code())
+                                    .json(serde_json::json!({ "error": e.to_string() })))
+                            }
+                        };
 
                         // Validate OAuth if configured
                         if let Some(oauth) = &server_clone.oauth_config {
@@ -60,7 +170,7 @@ pub fn create_app(cfg: &mut web::ServiceConfig, server: Arc<HttpMcpServer>) {
                             }
                         }
 
-                        let body_value = body.map(|json| json.into_inner());
+                        let body_value = json_body(&req, &body);
                         match handler(ctx, body_value).await {
                             Ok(response) => Ok(response),
                             Err(e) => {
@@ -82,15 +192,26 @@ pub fn create_app(cfg: &mut web::ServiceConfig, server: Arc<HttpMcpServer>) {
         let handler = endpoint.handler.clone();
         let server_clone = server.clone();
 
-        cfg.route(
+        add_route(
             &route,
             web::method(parse_http_method(&method)).to(
-                move |req: HttpRequest, multipart: Multipart| {
+                move |req: HttpRequest, payload: web::Payload| {
                     let handler = handler.clone();
                     let server_clone = server_clone.clone();
                     let ctx = create_request_context(&req);
+                    let limit = server_clone.config.multipart_payload_limit;
+                    let too_large =
+                        limit.filter(|&limit| declared_length(&req).is_some_and(|len| len > limit));
+                    // Bodies without a declared length fail mid-stream instead
+                    let multipart = Multipart::new(req.headers(), limit_payload(payload, limit));
 
                     async move {
+                        if let Some(limit) = too_large {
+                            let e = McpError::PayloadTooLarge(limit);
+                            return Ok(HttpResponse::build(e.status_code())
+                                .json(serde_json::json!({ "error": e.to_string() })));
+                        }
+
                         // Validate OAuth if configured
                         if let Some(oauth) = &server_clone.oauth_config {
                             if let Err(e) = oauth.validate_token(&ctx).await {
@@ -116,6 +237,21 @@ pub fn create_app(cfg: &mut web::ServiceConfig, server: Arc<HttpMcpServer>) {
             ),
         );
     }
+
+    for (route, routes) in resources {
+        let resource = routes
+            .into_iter()
+            .fold(web::resource(path(&route)), |resource, route| {
+                resource.route(route)
+            });
+        cfg.service(
+            resource
+                .app_data(data.clone())
+                .wrap(from_fn(apply_cors))
+                .wrap(from_fn(validate_origin))
+                .wrap(from_fn(compress_responses)),
+        );
+    }
 }
 
 /// Parse HTTP method string to actix-web Method
@@ -133,24 +269,39 @@ fn parse_http_method(method: &str) -> actix_web::http::Method {
 }
 
 /// POST /mcp - Handle JSON-RPC requests
-#[post("/mcp")]
 async fn handle_post(
     req: HttpRequest,
-    body: web::Json<JsonRpcRequest>,
+    payload: web::Payload,
     server: Data<Arc<HttpMcpServer>>,
-) -> Result<impl Responder> {
+) -> HttpResponse {
+    let server = server.get_ref().clone();
     let ctx = create_request_context(&req);
 
-    // Validate OAuth if configured
-    if let Some(oauth) = &server.oauth_config {
-        oauth.validate_token(&ctx).await?;
+    if let Err(e) = authenticate(&ctx, &server).await {
+        return e.to_http_response(None);
     }
 
-    // Validate JSON-RPC request
-    body.validate()?;
-
-    // Check if this is a notification (no id field)
-    let is_notification = body.id.is_none();
+    let body = match read_payload(&req, payload, server.config.mcp_payload_limit).await {
+        Ok(body) => body,
+        Err(e) => return e.to_http_response(None),
+    };
+    if let Some(messages) = parse_batch(&body) {
+        return match process_batch(ctx, messages, &server).await {
+            Ok(responses) if responses.is_empty() => HttpResponse::Accepted().finish(),
+            Ok(responses) => HttpResponse::Ok().json(responses),
+            Err(e) => e.to_http_response(None),
+        };
+    }
+    if let Some(response) = parse_response(&body) {
+        return match complete_client_request(&ctx, &server, response).await {
+            Ok(()) => HttpResponse::Accepted().finish(),
+            Err(e) => e.to_http_response(None),
+        };
+    }
+    let body = match JsonRpcRequest::parse(&body) {
+        Ok(body) => body,
+        Err((error, id)) => return json_response(&server, JsonRpcResponse::error(error, id)),
+    };
 
     // Check if client accepts SSE (streaming mode)
     let accept_sse = req
@@ -160,85 +311,808 @@ async fn handle_post(
         .map(|s| s.contains("text/event-stream"))
         .unwrap_or(false);
 
-    // Route and execute the request
-    let response = route_request(&body, &ctx, &server).await?;
+    let id = body.id.clone();
+    match process_post(ctx, body, accept_sse, server.clone()).await {
+        Ok(PostReply::Accepted) => HttpResponse::Accepted().finish(),
+        Ok(PostReply::Json(response, session_id)) => {
+            let mut resp = json_response(&server, response);
+            if let Some(value) = session_id.and_then(|id| header::HeaderValue::from_str(&id).ok()) {
+                resp.headers_mut()
+                    .insert(header::HeaderName::from_static(SESSION_ID_HEADER), value);
+            }
+            resp
+        }
+        Ok(PostReply::Stream(messages)) => HttpResponse::Ok()
+            .insert_header((header::CONTENT_TYPE, "text/event-stream"))
+            .insert_header((header::CACHE_CONTROL, "no-cache"))
+            .streaming(messages.map(|message| sse_message(&message))),
+        Err(e) => e.to_http_response(id),
+    }
+}
 
-    // Notifications MUST NOT receive a response per JSON-RPC 2.0 spec
-    if is_notification {
-        tracing::debug!(
-            "Notification received ({}), returning 204 No Content",
-            body.method
-        );
-        let mut resp = HttpResponse::NoContent();
-        if server.enable_cors {
-            resp.insert_header(("Access-Control-Allow-Origin", "*"));
+/// Read a request body, failing once it exceeds `limit` bytes
+async fn read_payload(req: &HttpRequest, mut payload: web::Payload, limit: usize) -> Result<Bytes> {
+    if declared_length(req).is_some_and(|len| len > limit) {
+        return Err(McpError::PayloadTooLarge(limit));
+    }
+
+    let mut body = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk
+            .map_err(|e| McpError::InvalidRequest(format!("Failed to read request body: {}", e)))?;
+        if body.len() + chunk.len() > limit {
+            return Err(McpError::PayloadTooLarge(limit));
         }
-        return Ok(resp.finish());
+        body.extend_from_slice(&chunk);
     }
+    Ok(body.freeze())
+}
 
-    // For SSE mode, broadcast response and return 202 Accepted
-    if accept_sse {
-        let subscriber_count = server.response_tx.receiver_count();
-        tracing::debug!("Broadcasting response to {} subscribers", subscriber_count);
+fn declared_length(req: &HttpRequest) -> Option<usize> {
+    req.headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+}
 
-        // If there are active SSE subscribers, send via broadcast
-        if subscriber_count > 0 {
-            let _ = server.response_tx.send(response);
-            let mut resp = HttpResponse::Accepted();
-            if server.enable_cors {
-                resp.insert_header(("Access-Control-Allow-Origin", "*"));
+/// Fail a body stream with an overflow error once it exceeds `limit` bytes
+fn limit_payload(
+    payload: web::Payload,
+    limit: Option<usize>,
+) -> impl Stream<Item = std::result::Result<Bytes, PayloadError>> {
+    let mut received = 0;
+    payload.map(move |chunk| {
+        let chunk = chunk?;
+        received += chunk.len();
+        match limit {
+            Some(limit) if received > limit => Err(PayloadError::Overflow),
+            _ => Ok(chunk),
+        }
+    })
+}
+
+/// A custom endpoint's body as JSON, if it was sent as JSON and parses
+fn json_body(req: &HttpRequest, body: &[u8]) -> Option<Value> {
+    let is_json = req
+        .mime_type()
+        .ok()
+        .flatten()
+        .is_some_and(|mime| mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON));
+    if !is_json || body.is_empty() {
+        return None;
+    }
+    serde_json::from_slice(body).ok()
+}
+
+/// Validate OAuth if configured
+pub(crate) async fn authenticate(ctx: &RequestContext, server: &HttpMcpServer) -> Result<()> {
+    match &server.oauth_config {
+        Some(oauth) => oauth.validate_token(ctx).await,
+        None => Ok(()),
+    }
+}
+
+/// How the MCP endpoint answers a POST, independent of the HTTP stack
+pub(crate) enum PostReply {
+    /// A notification, accepted with 202 and no body
+    Accepted,
+    /// A single JSON response, with the ID of the session a successful `initialize` started
+    Json(JsonRpcResponse, Option<String>),
+    /// An SSE stream of the request's notifications ending with its response
+    Stream(BoxStream<'static, JsonRpcMessage>),
+}
+
+/// Read a JSON-RPC batch: a body whose top-level value is an array
+pub(crate) fn parse_batch(body: &[u8]) -> Option<Vec<Value>> {
+    match serde_json::from_slice(body) {
+        Ok(Value::Array(messages)) => Some(messages),
+        _ => None,
+    }
+}
+
+/// Handle a JSON-RPC batch apart from any transport
+///
+/// Batches were removed in 2025-06-18, so they are refused in sessions that
+/// negotiated it. Each message is otherwise handled like one sent on its own,
+/// except that `initialize` may not be batched. Returns the responses to the
+/// batch's requests in order, which is empty when it only held notifications
+/// and responses.
+pub(crate) async fn process_batch(
+    ctx: RequestContext,
+    messages: Vec<Value>,
+    server: &HttpMcpServer,
+) -> Result<Vec<JsonRpcResponse>> {
+    let session = lookup_session(&ctx, server).await?;
+    let version = request_protocol_version(&ctx, session.as_ref())?;
+    if version >= ProtocolVersion::V2025_06_18 {
+        return Err(McpError::InvalidRequest(format!(
+            "JSON-RPC batches are not supported in protocol version {}",
+            version
+        )));
+    }
+    if messages.is_empty() {
+        return Err(McpError::InvalidRequest("Empty batch".to_string()));
+    }
+
+    let handled = messages.into_iter().map(|message| {
+        let ctx = ctx.clone();
+        async move {
+            let message = serde_json::to_vec(&message).unwrap_or_default();
+            if let Some(response) = parse_response(&message) {
+                if let Err(e) = complete_client_request(&ctx, server, response).await {
+                    tracing::debug!("Ignoring response in batch: {}", e);
+                }
+                return None;
             }
-            return Ok(resp.finish());
+            let req = match JsonRpcRequest::parse(&message) {
+                Ok(req) => req,
+                Err((error, id)) => return Some(JsonRpcResponse::error(error, id)),
+            };
+            if req.method == "initialize" {
+                let error = JsonRpcError::invalid_request("initialize cannot be part of a batch");
+                return Some(JsonRpcResponse::error(error, req.id));
+            }
+            handle_message(server, req, ctx).await
+        }
+    });
+    Ok(futures::future::join_all(handled)
+        .await
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+/// Complete a server-to-client request with the client's response to it
+///
+/// The response must come from the session the request was sent to.
+pub(crate) async fn complete_client_request(
+    ctx: &RequestContext,
+    server: &HttpMcpServer,
+    response: JsonRpcResponse,
+) -> Result<()> {
+    let session = lookup_session(ctx, server).await?.ok_or_else(|| {
+        McpError::InvalidRequest("Responses must include a session ID".to_string())
+    })?;
+    server.complete_request(&session.id, response);
+    Ok(())
+}
+
+/// Handle a parsed POST body, returning `Err` only for transport-level failures
+///
+/// Errors raised while handling the request itself become JSON-RPC error
+/// responses carrying the request ID.
+pub(crate) async fn process_post(
+    mut ctx: RequestContext,
+    body: JsonRpcRequest,
+    accept_sse: bool,
+    server: Arc<HttpMcpServer>,
+) -> Result<PostReply> {
+    // Check if this is a notification (no id field)
+    let is_notification = body.id.is_none();
+
+    // Initialize starts a new session; everything else must use a known one
+    let is_initialize = body.method == "initialize";
+    let session = if is_initialize {
+        let existing = ctx
+            .session_id
+            .as_deref()
+            .and_then(|id| server.sessions.get(id));
+        // Stateless servers answer initialize without issuing a session
+        if existing.is_none() && server.require_sessions {
+            ctx.session_id = Some(Session::generate_id());
         }
+        existing
+    } else {
+        lookup_session(&ctx, &server).await?
+    };
+    check_lifecycle(&body, session.as_ref(), &server)?;
+    let version = request_protocol_version(&ctx, session.as_ref())?;
+
+    // Notifications MUST NOT receive a response per JSON-RPC 2.0 spec
+    if is_notification {
+        route_notification(&body, &ctx, &server).await?;
+        return Ok(PostReply::Accepted);
+    }
+
+    // Stream the response with its related notifications.
+    // Initialize is always answered with JSON so the session ID header follows its outcome.
+    if accept_sse && server.sse_responses && !is_initialize {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        ctx.notifier = Some(RequestNotifier::for_request(tx, &body));
+
+        let messages = async_stream::stream! {
+            let execution = execute_request(&body, &ctx, &server, session.as_ref(), version);
+            tokio::pin!(execution);
+
+            let response = loop {
+                tokio::select! {
+                    Some(message) = rx.recv() => yield message,
+                    result = &mut execution => break result,
+                }
+            };
+
+            // Flush notifications sent just before the handler returned
+            while let Ok(message) = rx.try_recv() {
+                yield message;
+            }
+
+            let response = response.unwrap_or_else(|e| {
+                JsonRpcResponse::error(e.to_jsonrpc_error(), body.id.clone())
+            });
+            yield response.into();
+        };
 
-        // If no subscribers, fallback to direct response
-        tracing::warn!("No SSE subscribers, falling back to direct HTTP response");
+        return Ok(PostReply::Stream(messages.boxed()));
     }
 
-    // For non-SSE mode or fallback, return JSON response directly
+    let response = execute_request(&body, &ctx, &server, session.as_ref(), version).await?;
+    let session_id = match (&response.error, is_initialize) {
+        (None, true) => ctx.session_id,
+        _ => None,
+    };
+    Ok(PostReply::Json(response, session_id))
+}
+
+/// Handle one request or notification apart from any transport
+///
+/// Session, lifecycle and protocol version checks match POST `/mcp`, except that
+/// an `initialize` naming an unknown session starts it under that ID. Failures
+/// become JSON-RPC error responses; notifications never get a response unless
+/// they are invalid, which is answered with -32600 like a malformed POST body.
+pub(crate) async fn handle_message(
+    server: &HttpMcpServer,
+    req: JsonRpcRequest,
+    ctx: RequestContext,
+) -> Option<JsonRpcResponse> {
+    if let Err(error) = req.validate() {
+        return Some(JsonRpcResponse::error(error, req.id));
+    }
+
+    let result = async {
+        let session = if req.method == "initialize" {
+            ctx.session_id
+                .as_deref()
+                .and_then(|id| server.sessions.get(id))
+        } else {
+            lookup_session(&ctx, server).await?
+        };
+        check_lifecycle(&req, session.as_ref(), server)?;
+        let version = request_protocol_version(&ctx, session.as_ref())?;
+
+        if req.id.is_none() {
+            route_notification(&req, &ctx, server).await?;
+            return Ok(None);
+        }
+        execute_request(&req, &ctx, server, session.as_ref(), version)
+            .await
+            .map(Some)
+    }
+    .await;
+
+    result.unwrap_or_else(|e| match req.id {
+        Some(id) => Some(JsonRpcResponse::error(e.to_jsonrpc_error(), Some(id))),
+        None => {
+            tracing::warn!("Notification {} failed: {}", req.method, e);
+            None
+        }
+    })
+}
+
+/// JSON response for a JSON-RPC message, with the status chosen by the server's error policy
+fn json_response(server: &HttpMcpServer, response: JsonRpcResponse) -> HttpResponse {
     let mut resp = HttpResponse::Ok();
-    if server.enable_cors {
-        resp.insert_header(("Access-Control-Allow-Origin", "*"));
+    if let Some(error) = &response.error {
+        resp.status(server.error_status.status_for(error));
+    }
+    resp.json(response)
+}
+
+/// Route a request and shape the result for the negotiated protocol version
+///
+/// Handler errors become JSON-RPC error responses carrying the request ID.
+/// Closing the session cancels the request with [`McpError::SessionNotFound`],
+/// and requests reaching here once shutdown has begun fail with
+/// [`McpError::ShuttingDown`].
+async fn execute_request(
+    req: &JsonRpcRequest,
+    ctx: &RequestContext,
+    server: &HttpMcpServer,
+    session: Option<&Session>,
+    version: ProtocolVersion,
+) -> Result<JsonRpcResponse> {
+    // Count the request before checking for shutdown, so a drain starting in
+    // between either waits for it or is seen here
+    let in_flight = server.shutdown_state.track();
+    if !server.shutdown_state.is_running() {
+        drop(in_flight);
+        return Err(McpError::ShuttingDown);
+    }
+    // Handlers and the tool policy see the session's attributes
+    let ctx = &RequestContext {
+        session_attributes: session.map(|s| s.attributes.clone()).unwrap_or_default(),
+        ..ctx.clone()
+    };
+    let routed = match session {
+        Some(session) => tokio::select! {
+            response = route_request(req, ctx, server) => response,
+            _ = session.closed() => return Err(McpError::SessionNotFound(session.id.clone())),
+        },
+        None => route_request(req, ctx, server).await,
+    };
+    let mut response = routed.unwrap_or_else(|e| {
+        tracing::debug!("Request {} failed: {}", req.method, e);
+        JsonRpcResponse::error(e.to_jsonrpc_error(), req.id.clone())
+    });
+    if let Some(result) = response.result.as_mut() {
+        adapt_result(&req.method, result, version);
     }
-    Ok(resp.json(response))
+    Ok(response)
+}
+
+/// Encode a JSON-RPC message as an SSE `message` event
+pub(crate) fn sse_message(message: &JsonRpcMessage) -> serde_json::Result<Bytes> {
+    let json = serde_json::to_string(message)?;
+    tracing::debug!("Sending message via SSE: {}", json);
+    Ok(SseEvent::new(json).to_bytes())
+}
+
+/// Final SSE event sent on open streams when the server shuts down
+pub(crate) fn shutdown_event() -> Bytes {
+    SseEvent::new("{}").with_event("shutdown").to_bytes()
 }
 
 /// GET /mcp - SSE stream for server-to-client messages
-#[get("/mcp")]
 async fn handle_get(req: HttpRequest, server: Data<Arc<HttpMcpServer>>) -> Result<impl Responder> {
     let ctx = create_request_context(&req);
+    authenticate(&ctx, &server).await?;
+
+    let events = session_events(&ctx, &server).await?;
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, "text/event-stream"))
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(events.map(Ok::<_, actix_web::Error>)))
+}
+
+/// SSE events for the GET stream
+///
+/// While open, the stream is the session's outbound channel: notifications and
+/// requests from [`HttpMcpServer::notify`] and [`HttpMcpServer::send_request`]
+/// arrive on it. The stream ends when the session closes, or with a final
+/// `shutdown` event when the server shuts down.
+pub(crate) async fn session_events(
+    ctx: &RequestContext,
+    server: &Arc<HttpMcpServer>,
+) -> Result<BoxStream<'static, Bytes>> {
+    if !server.shutdown_state.is_running() {
+        return Err(McpError::ShuttingDown);
+    }
+    let session = lookup_session(ctx, server).await?;
+
+    // Check for Last-Event-ID header for resumption
+    let _last_event_id = ctx.get_header("Last-Event-ID");
+
+    let shutdown = server.shutdown_state.closed();
+
+    tracing::debug!("SSE stream connected");
+
+    let Some(session) = session else {
+        // Without a session there is nothing to deliver until shutdown
+        let events = async_stream::stream! {
+            shutdown.await;
+            yield shutdown_event();
+        };
+        return Ok(events.boxed());
+    };
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    server
+        .session_streams
+        .write()
+        .unwrap()
+        .insert(session.id.clone(), tx.clone());
+    let guard = SessionStreamGuard {
+        server: server.clone(),
+        session_id: session.id.clone(),
+        tx,
+    };
+
+    let events = async_stream::stream! {
+        let _guard = guard;
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                biased;
+                _ = &mut shutdown => {
+                    yield shutdown_event();
+                    break;
+                }
+                _ = session.closed() => break,
+                Some(message) = rx.recv() => {
+                    if let Ok(event) = sse_message(&message) {
+                        yield event;
+                    }
+                }
+            }
+        }
+    };
+
+    Ok(events.boxed())
+}
+
+/// Unregisters a GET stream from the session's outbound channel once the client drops it
+///
+/// A newer stream for the same session replaces the entry and is left in place.
+struct SessionStreamGuard {
+    server: Arc<HttpMcpServer>,
+    session_id: String,
+    tx: tokio::sync::mpsc::UnboundedSender<JsonRpcMessage>,
+}
+
+impl Drop for SessionStreamGuard {
+    fn drop(&mut self) {
+        let mut streams = self.server.session_streams.write().unwrap();
+        if streams
+            .get(&self.session_id)
+            .is_some_and(|tx| tx.same_channel(&self.tx))
+        {
+            streams.remove(&self.session_id);
+        }
+    }
+}
+
+/// DELETE /mcp - Terminate a session
+async fn handle_delete(
+    req: HttpRequest,
+    server: Data<Arc<HttpMcpServer>>,
+) -> Result<impl Responder> {
+    let ctx = create_request_context(&req);
+    authenticate(&ctx, &server).await?;
+    terminate_session(&ctx, &server).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// End the session named by the request
+pub(crate) async fn terminate_session(ctx: &RequestContext, server: &HttpMcpServer) -> Result<()> {
+    let session_id = ctx
+        .session_id
+        .as_deref()
+        .ok_or_else(|| McpError::InvalidRequest("Missing Mcp-Session-Id header".to_string()))?;
+
+    server
+        .end_session(session_id, SessionCloseReason::Terminated)
+        .await
+        .ok_or_else(|| McpError::SessionNotFound(session_id.to_string()))?;
+    Ok(())
+}
+
+/// GET /mcp/ws - Open a session over a WebSocket
+///
+/// Each text frame carries one JSON-RPC message in either direction. The socket
+/// is a single session that ends when either side closes it.
+async fn handle_websocket(
+    req: HttpRequest,
+    body: web::Payload,
+    server: Data<Arc<HttpMcpServer>>,
+) -> actix_web::Result<HttpResponse> {
+    let mut ctx = create_request_context(&req);
 
     // Validate OAuth if configured
     if let Some(oauth) = &server.oauth_config {
         oauth.validate_token(&ctx).await?;
     }
 
-    // Check for Last-Event-ID header for resumption
-    let _last_event_id = req
-        .headers()
-        .get("Last-Event-ID")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string());
+    if !server.shutdown_state.is_running() {
+        return Err(McpError::ShuttingDown.into());
+    }
 
-    // Subscribe to response broadcast channel
-    let mut rx = server.response_tx.subscribe();
+    let (response, mut socket, stream) = actix_ws::handle(&req, body)?;
+    let mut stream = stream
+        .max_frame_size(server.config.mcp_payload_limit)
+        .aggregate_continuations()
+        .max_continuation_size(server.config.mcp_payload_limit);
+
+    let server = server.get_ref().clone();
+    let mut session = Session::new(Session::generate_id(), ProtocolVersion::LATEST, None);
+    session.state = SessionState::Uninitialized;
+    server.sessions.insert(session.clone());
+    ctx.session_id = Some(session.id.clone());
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    server
+        .session_streams
+        .write()
+        .unwrap()
+        .insert(session.id.clone(), tx.clone());
+    tracing::debug!("WebSocket session {} connected", session.id);
+    let shutdown = server.shutdown_state.closed();
+
+    actix_web::rt::spawn(async move {
+        tokio::pin!(shutdown);
+        let mut close_reason = None;
+        loop {
+            tokio::select! {
+                incoming = stream.recv() => match incoming {
+                    Some(Ok(AggregatedMessage::Text(text))) => {
+                        dispatch_message(&server, ctx.clone(), text.as_bytes(), &tx).await;
+                    }
+                    Some(Ok(AggregatedMessage::Binary(bytes))) => {
+                        dispatch_message(&server, ctx.clone(), &bytes, &tx).await;
+                    }
+                    Some(Ok(AggregatedMessage::Ping(bytes))) => {
+                        if socket.pong(&bytes).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(AggregatedMessage::Pong(_))) => {}
+                    Some(Ok(AggregatedMessage::Close(_))) | None => break,
+                    Some(Err(e)) => {
+                        tracing::debug!("WebSocket session {} failed: {}", session.id, e);
+                        break;
+                    }
+                },
+                Some(message) = rx.recv() => {
+                    let text = serde_json::to_string(&message).unwrap_or_default();
+                    if socket.text(text).await.is_err() {
+                        break;
+                    }
+                }
+                _ = &mut shutdown => {
+                    close_reason = Some(actix_ws::CloseReason {
+                        code: actix_ws::CloseCode::Away,
+                        description: Some("Server shutting down".to_string()),
+                    });
+                    break;
+                }
+                _ = session.closed() => break,
+            }
+        }
 
-    tracing::debug!("SSE stream connected");
+        tracing::debug!("WebSocket session {} disconnected", session.id);
+        server
+            .end_session(&session.id, SessionCloseReason::Terminated)
+            .await;
+        let _ = socket.close(close_reason).await;
+    });
+
+    Ok(response)
+}
+
+/// Query string of legacy `POST /messages` requests
+#[derive(Debug, serde::Deserialize)]
+struct LegacyMessageQuery {
+    #[serde(rename = "sessionId")]
+    session_id: String,
+}
+
+/// Ends a legacy session once the client drops its SSE stream
+struct LegacyStreamGuard {
+    server: Arc<HttpMcpServer>,
+    session_id: String,
+}
+
+impl Drop for LegacyStreamGuard {
+    fn drop(&mut self) {
+        let server = self.server.clone();
+        let session_id = std::mem::take(&mut self.session_id);
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                server
+                    .end_session(&session_id, SessionCloseReason::Terminated)
+                    .await;
+            });
+        }
+    }
+}
+
+/// GET /sse - Open a session on the legacy HTTP+SSE transport (2024-11-05)
+///
+/// The first event names the endpoint for the client's messages; responses and
+/// notifications for the session follow on the same stream.
+async fn handle_legacy_sse(
+    req: HttpRequest,
+    server: Data<Arc<HttpMcpServer>>,
+) -> Result<impl Responder> {
+    let ctx = create_request_context(&req);
+
+    // Validate OAuth if configured
+    if let Some(oauth) = &server.oauth_config {
+        oauth.validate_token(&ctx).await?;
+    }
+
+    if !server.shutdown_state.is_running() {
+        return Err(McpError::ShuttingDown);
+    }
+
+    let server = server.get_ref().clone();
+    let mut session = Session::new(Session::generate_id(), ProtocolVersion::V2024_11_05, None);
+    session.state = SessionState::Uninitialized;
+    server.sessions.insert(session.clone());
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    server
+        .session_streams
+        .write()
+        .unwrap()
+        .insert(session.id.clone(), tx);
+
+    tracing::debug!("Legacy SSE session {} connected", session.id);
+    // Sibling of this resource, wherever the server is mounted
+    let endpoint = format!(
+        "{}messages?sessionId={}",
+        req.path().trim_end_matches("sse"),
+        session.id
+    );
+    let shutdown = server.shutdown_state.closed();
+    let guard = LegacyStreamGuard {
+        server,
+        session_id: session.id.clone(),
+    };
 
-    // Create SSE stream from broadcast channel
     let event_stream = async_stream::stream! {
-        while let Ok(response) = rx.recv().await {
-            if let Ok(json) = serde_json::to_string(&response) {
-                tracing::debug!("Sending response via SSE: {}", json);
-                // Send as "message" event with the JSON-RPC response
-                yield Ok::<_, actix_web::Error>(sse::Event::Data(
-                    sse::Data::new(json)
-                ));
+        let _guard = guard;
+        tokio::pin!(shutdown);
+        yield Ok(SseEvent::new(endpoint).with_event("endpoint").to_bytes());
+        loop {
+            tokio::select! {
+                biased;
+                Some(message) = rx.recv() => yield sse_message(&message),
+                _ = &mut shutdown => {
+                    yield Ok(shutdown_event());
+                    break;
+                }
+                _ = session.closed() => break,
             }
         }
     };
 
-    Ok(sse::Sse::from_stream(event_stream))
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, "text/event-stream"))
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(event_stream))
+}
+
+/// POST /messages - Receive a message for a legacy HTTP+SSE session
+///
+/// Messages are acknowledged with 202 and answered on the session's SSE stream.
+async fn handle_legacy_message(
+    req: HttpRequest,
+    query: web::Query<LegacyMessageQuery>,
+    payload: web::Payload,
+    server: Data<Arc<HttpMcpServer>>,
+) -> HttpResponse {
+    let mut ctx = create_request_context(&req);
+    ctx.session_id = Some(query.into_inner().session_id);
+    process_legacy_message(&req, ctx, payload, server.get_ref().clone())
+        .await
+        .unwrap_or_else(|e| e.to_http_response(None))
+}
+
+async fn process_legacy_message(
+    req: &HttpRequest,
+    ctx: RequestContext,
+    payload: web::Payload,
+    server: Arc<HttpMcpServer>,
+) -> Result<HttpResponse> {
+    // Validate OAuth if configured
+    if let Some(oauth) = &server.oauth_config {
+        oauth.validate_token(&ctx).await?;
+    }
+
+    let session_id = ctx.session_id.clone().unwrap_or_default();
+    let tx = server
+        .session_streams
+        .read()
+        .unwrap()
+        .get(&session_id)
+        .cloned()
+        .ok_or_else(|| McpError::SessionNotFound(session_id.clone()))?;
+    // Expired sessions are closed here and reported as not found
+    lookup_session(&ctx, &server).await?;
+
+    let body = read_payload(req, payload, server.config.mcp_payload_limit).await?;
+    dispatch_message(&server, ctx, &body, &tx).await;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+/// Parse a client's response to a server-to-client request
+///
+/// Only objects carrying `result` or `error` (and no `method`) are responses;
+/// anything else is validated as a request so malformed messages get -32600.
+pub(crate) fn parse_response(message: &[u8]) -> Option<JsonRpcResponse> {
+    let value: Value = serde_json::from_slice(message).ok()?;
+    let object = value.as_object()?;
+    if object.contains_key("method")
+        || !(object.contains_key("result") || object.contains_key("error"))
+    {
+        return None;
+    }
+    serde_json::from_value(value).ok()
+}
+
+/// Handle one message on a bidirectional transport (legacy SSE, WebSocket, stdio)
+///
+/// `ctx` must name the session. Responses and notifications go out through `tx`;
+/// requests run concurrently, except `initialize`, which completes before the
+/// next message is read so the lifecycle check sees its outcome. Responses from
+/// the client complete pending [`HttpMcpServer::send_request`] calls, and a
+/// batch is answered with one batch of responses.
+pub(crate) async fn dispatch_message(
+    server: &Arc<HttpMcpServer>,
+    mut ctx: RequestContext,
+    message: &[u8],
+    tx: &tokio::sync::mpsc::UnboundedSender<JsonRpcMessage>,
+) {
+    if let Some(response) = parse_response(message) {
+        if let Some(session_id) = &ctx.session_id {
+            server.complete_request(session_id, response);
+        }
+        return;
+    }
+
+    if let Some(messages) = parse_batch(message) {
+        let server = server.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let reply = match process_batch(ctx, messages, &server).await {
+                Ok(responses) if responses.is_empty() => return,
+                Ok(responses) => JsonRpcMessage::Batch(responses),
+                Err(e) => JsonRpcResponse::error(e.to_jsonrpc_error(), None).into(),
+            };
+            let _ = tx.send(reply);
+        });
+        return;
+    }
+
+    let req = match JsonRpcRequest::parse(message) {
+        Ok(req) => req,
+        Err((error, id)) => {
+            let _ = tx.send(JsonRpcResponse::error(error, id).into());
+            return;
+        }
+    };
+
+    let session = ctx
+        .session_id
+        .as_deref()
+        .and_then(|id| server.sessions.touch(id));
+    let Some(session) = session else {
+        let error = McpError::SessionNotFound(ctx.session_id.unwrap_or_default());
+        if req.id.is_some() {
+            let _ = tx.send(JsonRpcResponse::error(error.to_jsonrpc_error(), req.id).into());
+        }
+        return;
+    };
+
+    if let Err(e) = check_lifecycle(&req, Some(&session), server) {
+        if req.id.is_some() {
+            let _ = tx.send(JsonRpcResponse::error(e.to_jsonrpc_error(), req.id.clone()).into());
+        }
+        return;
+    }
+
+    if req.id.is_none() {
+        if let Err(e) = route_notification(&req, &ctx, server).await {
+            tracing::warn!("Notification {} failed: {}", req.method, e);
+        }
+        return;
+    }
+
+    ctx.notifier = Some(RequestNotifier::for_request(tx.clone(), &req));
+    let is_initialize = req.method == "initialize";
+    let version = session.protocol_version;
+    let server = server.clone();
+    let tx = tx.clone();
+    let execution = async move {
+        if let Ok(response) = execute_request(&req, &ctx, &server, Some(&session), version).await {
+            let _ = tx.send(response.into());
+        }
+    };
+
+    if is_initialize {
+        execution.await;
+    } else {
+        tokio::spawn(execution);
+    }
 }
 
 /// Route JSON-RPC request to appropriate handler
@@ -251,19 +1125,15 @@ async fn route_request(
 
     match req.method.as_str() {
         // Lifecycle
-        "initialize" => {
-            handle_initialize(req, server.server_info.clone(), server.capabilities.clone())
-        }
+        "initialize" => handle_initialize(req, ctx, server),
         "ping" => handle_ping(req),
 
-        // Notifications
-        "notifications/initialized" => handle_notifications_initialized(req),
-
         // Resources
         "resources/list" => handle_resources_list(req, ctx, server).await,
         "resources/read" => handle_resources_read(req, ctx, server).await,
         "resources/templates/list" => handle_resources_templates(req, ctx, server).await,
         "resources/subscribe" => handle_resources_subscribe(req, ctx, server).await,
+        "resources/unsubscribe" => handle_resources_unsubscribe(req, ctx, server).await,
 
         // Tools
         "tools/list" => handle_tools_list(req, ctx, server).await,
@@ -289,20 +1159,40 @@ async fn handle_resources_list(
     ctx: &RequestContext,
     server: &HttpMcpServer,
 ) -> Result<JsonRpcResponse> {
+    // Params are optional for list requests
     let params: ResourcesListParams =
-        serde_json::from_value(req.params.clone().unwrap_or(Value::Null))
-            .unwrap_or(ResourcesListParams { cursor: None });
+        serde_json::from_value(req.params.clone().unwrap_or_else(|| serde_json::json!({})))
+            .map_err(|e| McpError::InvalidParams(format!("Invalid params: {}", e)))?;
+
+    // Registered handlers in URI order, then providers; a page ends at the
+    // first source with more to list
+    let mut registered: Vec<_> = server.resources.iter().collect();
+    registered.sort_by(|a, b| a.0.cmp(b.0));
+    let sources = registered.len() + server.resource_providers.len();
+    let (start, mut cursor) = decode_cursor(params.cursor.as_deref(), sources)?;
 
-    // Collect all resources from registered handlers
     let mut all_resources = Vec::new();
-    for registered in server.resources.values() {
-        let (resources, _) = (registered.list_handler)(params.cursor.clone(), ctx.clone()).await?;
+    let mut next_cursor = None;
+    for index in start..sources {
+        let (resources, next) = match registered.get(index) {
+            Some((_, registered)) => (registered.list_handler)(cursor.take(), ctx.clone()).await?,
+            None => {
+                let provider = &server.resource_providers[index - registered.len()];
+                provider
+                    .list_resources(cursor.take().as_deref(), ctx)
+                    .await?
+            }
+        };
         all_resources.extend(resources);
+        if let Some(next) = next {
+            next_cursor = Some(encode_cursor(index, next));
+            break;
+        }
     }
 
     let result = ResourcesListResult {
         resources: all_resources,
-        next_cursor: None,
+        next_cursor,
     };
 
     Ok(JsonRpcResponse::success(
@@ -320,11 +1210,22 @@ async fn handle_resources_read(
         serde_json::from_value(req.params.clone().unwrap_or(Value::Null))
             .map_err(|e| McpError::InvalidParams(format!("Invalid params: {}", e)))?;
 
-    // Try to find matching resource handler
+    // Try every source; those that do not know the URI report ResourceNotFound
     let mut contents = Vec::new();
     for registered in server.resources.values() {
-        let result = (registered.read_handler)(params.uri.clone(), ctx.clone()).await?;
-        contents.extend(result);
+        match (registered.read_handler)(params.uri.clone(), ctx.clone()).await {
+            Ok(result) => contents.extend(result),
+            Err(McpError::ResourceNotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    for provider in &server.resource_providers {
+        match provider.read_resource(&params.uri, ctx).await {
+            Ok(result) => contents.extend(result),
+            Err(McpError::ResourceNotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
     }
 
     if contents.is_empty() {
@@ -341,23 +1242,88 @@ async fn handle_resources_read(
 
 async fn handle_resources_templates(
     req: &JsonRpcRequest,
-    _ctx: &RequestContext,
-    _server: &HttpMcpServer,
+    ctx: &RequestContext,
+    server: &HttpMcpServer,
 ) -> Result<JsonRpcResponse> {
-    // Resource templates are not supported in the new function-based API
+    // Resource templates are only available through resource providers
+    let mut templates = Vec::new();
+    for provider in &server.resource_providers {
+        templates.extend(provider.list_templates(ctx).await?);
+    }
+
+    let result = ResourceTemplatesListResult {
+        resource_templates: templates,
+        next_cursor: None,
+    };
+
     Ok(JsonRpcResponse::success(
-        serde_json::json!({ "resourceTemplates": [] }),
+        serde_json::to_value(result)?,
         req.id.clone(),
     ))
 }
 
 async fn handle_resources_subscribe(
     req: &JsonRpcRequest,
-    _ctx: &RequestContext,
-    _server: &HttpMcpServer,
+    ctx: &RequestContext,
+    server: &HttpMcpServer,
 ) -> Result<JsonRpcResponse> {
-    // Resource subscription is not supported in the new function-based API
-    Ok(JsonRpcResponse::success(Value::Null, req.id.clone()))
+    let params: ResourcesSubscribeParams =
+        serde_json::from_value(req.params.clone().unwrap_or(Value::Null))
+            .map_err(|e| McpError::InvalidParams(format!("Invalid params: {}", e)))?;
+
+    let mut accepted = false;
+    for provider in subscribable_providers(server) {
+        match provider.subscribe(&params.uri, ctx).await {
+            Ok(()) => accepted = true,
+            Err(McpError::ResourceNotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    if !accepted {
+        return Err(McpError::ResourceNotFound(params.uri));
+    }
+
+    Ok(JsonRpcResponse::success(
+        serde_json::json!({}),
+        req.id.clone(),
+    ))
+}
+
+async fn handle_resources_unsubscribe(
+    req: &JsonRpcRequest,
+    ctx: &RequestContext,
+    server: &HttpMcpServer,
+) -> Result<JsonRpcResponse> {
+    let params: ResourcesSubscribeParams =
+        serde_json::from_value(req.params.clone().unwrap_or(Value::Null))
+            .map_err(|e| McpError::InvalidParams(format!("Invalid params: {}", e)))?;
+
+    let mut accepted = false;
+    for provider in subscribable_providers(server) {
+        match provider.unsubscribe(&params.uri, ctx).await {
+            Ok(()) => accepted = true,
+            Err(McpError::ResourceNotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    if !accepted {
+        return Err(McpError::ResourceNotFound(params.uri));
+    }
+
+    Ok(JsonRpcResponse::success(
+        serde_json::json!({}),
+        req.id.clone(),
+    ))
+}
+
+/// Resource providers that accept `resources/subscribe`
+fn subscribable_providers(
+    server: &HttpMcpServer,
+) -> impl Iterator<Item = &Arc<dyn ResourceProvider>> {
+    server
+        .resource_providers
+        .iter()
+        .filter(|provider| provider.supports_subscribe())
 }
 
 // ============================================================================
@@ -366,16 +1332,20 @@ async fn handle_resources_subscribe(
 
 async fn handle_tools_list(
     req: &JsonRpcRequest,
-    _ctx: &RequestContext,
+    ctx: &RequestContext,
     server: &HttpMcpServer,
 ) -> Result<JsonRpcResponse> {
     // Collect all registered tools
-    let tools: Vec<Tool> = server
+    let mut tools: Vec<Tool> = server
         .tools
         .values()
         .map(|registered| registered.meta.clone())
         .collect();
 
+    for provider in &server.tool_providers {
+        tools.extend(provider.list_tools(ctx).await?);
+    }
+
     let result = ToolsListResult {
         tools,
         next_cursor: None,
@@ -395,24 +1365,26 @@ async fn handle_tools_call(
     let params: ToolsCallParams = serde_json::from_value(req.params.clone().unwrap_or(Value::Null))
         .map_err(|e| McpError::InvalidParams(format!("Invalid params: {}", e)))?;
 
-    // Find the registered tool
-    let registered = server
-        .tools
-        .get(&params.name)
-        .ok_or_else(|| McpError::ToolNotFound(params.name.clone()))?;
+    // Function-registered tools take precedence over providers
+    let result = if let Some(registered) = server.tools.get(&params.name) {
+        if let Some(policy) = &server.tool_policy {
+            policy(&registered.meta, ctx)?;
+        }
 
-    // Call the tool handler
-    let result_value =
-        (registered.handler)(params.arguments.unwrap_or_default(), ctx.clone()).await?;
+        (registered.handler)(params.arguments.unwrap_or_default(), ctx.clone()).await?
+    } else {
+        let (provider, meta) = find_tool_provider(&params.name, ctx, server)
+            .await?
+            .ok_or_else(|| McpError::ToolNotFound(params.name.clone()))?;
 
-    // Convert result to ToolContent
-    let content = vec![ToolContent::Text {
-        text: result_value.to_string(),
-    }];
+        if let Some(policy) = &server.tool_policy {
+            policy(&meta, ctx)?;
+        }
 
-    let result = ToolsCallResult {
-        content,
-        is_error: None,
+        provider
+            .call_tool(&params.name, params.arguments, ctx)
+            .await?
+            .into_tool_result()?
     };
 
     Ok(JsonRpcResponse::success(
@@ -421,25 +1393,65 @@ async fn handle_tools_call(
     ))
 }
 
+/// Find the provider that lists a tool with the given name
+async fn find_tool_provider<'a>(
+    name: &str,
+    ctx: &RequestContext,
+    server: &'a HttpMcpServer,
+) -> Result<Option<(&'a Arc<dyn ToolProvider>, Tool)>> {
+    for provider in &server.tool_providers {
+        let tools = provider.list_tools(ctx).await?;
+        if let Some(tool) = tools.into_iter().find(|tool| tool.name == name) {
+            return Ok(Some((provider, tool)));
+        }
+    }
+    Ok(None)
+}
+
 // ============================================================================
 // Prompt Handlers
 // ============================================================================
 
 async fn handle_prompts_list(
     req: &JsonRpcRequest,
-    _ctx: &RequestContext,
+    ctx: &RequestContext,
     server: &HttpMcpServer,
 ) -> Result<JsonRpcResponse> {
-    // Collect all registered prompts
-    let prompts: Vec<Prompt> = server
-        .prompts
-        .values()
-        .map(|registered| registered.meta.clone())
-        .collect();
+    // Params are optional for list requests
+    let params: PromptsListParams =
+        serde_json::from_value(req.params.clone().unwrap_or_else(|| serde_json::json!({})))
+            .map_err(|e| McpError::InvalidParams(format!("Invalid params: {}", e)))?;
+
+    // Registered prompts form the first source and are listed in one page;
+    // each provider after them is its own source
+    let sources = 1 + server.prompt_providers.len();
+    let (start, mut cursor) = decode_cursor(params.cursor.as_deref(), sources)?;
+
+    let mut prompts: Vec<Prompt> = Vec::new();
+    if start == 0 {
+        prompts.extend(
+            server
+                .prompts
+                .values()
+                .map(|registered| registered.meta.clone()),
+        );
+        cursor = None;
+    }
+
+    let mut next_cursor = None;
+    for index in start.max(1)..sources {
+        let provider = &server.prompt_providers[index - 1];
+        let (provided, next) = provider.list_prompts(cursor.take().as_deref(), ctx).await?;
+        prompts.extend(provided);
+        if let Some(next) = next {
+            next_cursor = Some(encode_cursor(index, next));
+            break;
+        }
+    }
 
     let result = PromptsListResult {
         prompts,
-        next_cursor: None,
+        next_cursor,
     };
 
     Ok(JsonRpcResponse::success(
@@ -457,15 +1469,12 @@ async fn handle_prompts_get(
         serde_json::from_value(req.params.clone().unwrap_or(Value::Null))
             .map_err(|e| McpError::InvalidParams(format!("Invalid params: {}", e)))?;
 
-    // Find the registered prompt
-    let registered = server
-        .prompts
-        .get(&params.name)
-        .ok_or_else(|| McpError::PromptNotFound(params.name.clone()))?;
-
-    // Call the prompt handler
-    let (description, messages) =
-        (registered.handler)(params.name.clone(), params.arguments, ctx.clone()).await?;
+    // Function-registered prompts take precedence over providers
+    let (description, messages) = if let Some(registered) = server.prompts.get(&params.name) {
+        (registered.handler)(params.name.clone(), params.arguments, ctx.clone()).await?
+    } else {
+        get_provider_prompt(&params, ctx, server).await?
+    };
 
     let result = PromptsGetResult {
         description,
@@ -478,17 +1487,63 @@ async fn handle_prompts_get(
     ))
 }
 
+/// Ask each prompt provider in turn; those that do not know the prompt report PromptNotFound
+async fn get_provider_prompt(
+    params: &PromptsGetParams,
+    ctx: &RequestContext,
+    server: &HttpMcpServer,
+) -> Result<(Option<String>, Vec<PromptMessage>)> {
+    for provider in &server.prompt_providers {
+        match provider
+            .get_prompt(&params.name, params.arguments.clone(), ctx)
+            .await
+        {
+            Err(McpError::PromptNotFound(_)) => continue,
+            result => return result,
+        }
+    }
+    Err(McpError::PromptNotFound(params.name.clone()))
+}
+
 // ============================================================================
 // Notification Handlers
 // ============================================================================
 
-fn handle_notifications_initialized(req: &JsonRpcRequest) -> Result<JsonRpcResponse> {
+/// Handle a JSON-RPC notification; notifications never produce a response
+async fn route_notification(
+    req: &JsonRpcRequest,
+    ctx: &RequestContext,
+    server: &HttpMcpServer,
+) -> Result<()> {
+    tracing::debug!("Notification received: method={}", req.method);
+
+    match req.method.as_str() {
+        "notifications/initialized" => handle_notifications_initialized(ctx, server).await,
+        // Unknown notifications are ignored
+        _ => Ok(()),
+    }
+}
+
+async fn handle_notifications_initialized(
+    ctx: &RequestContext,
+    server: &HttpMcpServer,
+) -> Result<()> {
     tracing::debug!("Client initialized notification received");
-    // Return empty object instead of null
-    Ok(JsonRpcResponse::success(
-        serde_json::json!({}),
-        req.id.clone(),
-    ))
+
+    let Some(session_id) = &ctx.session_id else {
+        return Ok(());
+    };
+    let Some(session) = server.sessions.set_state(session_id, SessionState::Ready) else {
+        return Ok(());
+    };
+
+    if let (Some(hook), Some(client_info)) = (&server.on_initialized, session.client_info) {
+        if let Err(e) = hook(client_info, ctx.clone()).await {
+            tracing::warn!("on_initialized hook failed: {}", e);
+        }
+    }
+
+    Ok(())
 }
 
 // ============================================================================
@@ -511,11 +1566,130 @@ fn handle_logging_set_level(req: &JsonRpcRequest) -> Result<JsonRpcResponse> {
 // Utilities
 // ============================================================================
 
+/// Position in a list spread over several sources: the source to resume from
+/// and that source's own cursor
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ListCursor {
+    source: usize,
+    cursor: String,
+}
+
+/// Opaque cursor handed to clients for the next page of a list
+fn encode_cursor(source: usize, cursor: String) -> String {
+    use base64::Engine;
+    let json = serde_json::to_vec(&ListCursor { source, cursor }).unwrap_or_default();
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json)
+}
+
+/// Decode a client's cursor into the source index and its inner cursor
+fn decode_cursor(cursor: Option<&str>, sources: usize) -> Result<(usize, Option<String>)> {
+    use base64::Engine;
+    let Some(cursor) = cursor else {
+        return Ok((0, None));
+    };
+    let invalid = || McpError::InvalidParams(format!("Invalid cursor: {}", cursor));
+    let json = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(cursor)
+        .map_err(|_| invalid())?;
+    let decoded: ListCursor = serde_json::from_slice(&json).map_err(|_| invalid())?;
+    if decoded.source >= sources {
+        return Err(invalid());
+    }
+    Ok((decoded.source, Some(decoded.cursor)))
+}
+
+/// Resolve the session named by the `Mcp-Session-Id` header
+///
+/// Requests without the header have no session. An unknown or expired ID
+/// yields 404 so the client knows to re-initialize.
+async fn lookup_session(ctx: &RequestContext, server: &HttpMcpServer) -> Result<Option<Session>> {
+    let Some(id) = &ctx.session_id else {
+        return Ok(None);
+    };
+    let not_found = || McpError::SessionNotFound(id.clone());
+
+    let session = server.sessions.get(id).ok_or_else(not_found)?;
+    if let Some(reason) = session.expiry(server.session_idle_timeout, server.session_max_lifetime) {
+        server.end_session(id, reason).await;
+        return Err(not_found());
+    }
+
+    server.sessions.touch(id).map(Some).ok_or_else(not_found)
+}
+
+/// Enforce the session lifecycle for a request
+///
+/// Requests without a session are treated as uninitialized unless the server
+/// is stateless. Notifications other than `notifications/initialized`, such as
+/// cancellation and progress, are accepted in any state of an open session.
+/// Every request is refused once the server is shutting down.
+fn check_lifecycle(
+    req: &JsonRpcRequest,
+    session: Option<&Session>,
+    server: &HttpMcpServer,
+) -> Result<()> {
+    if !server.shutdown_state.is_running() {
+        return Err(McpError::ShuttingDown);
+    }
+
+    let state = match session {
+        Some(session) => session.state,
+        None if server.require_sessions => SessionState::Uninitialized,
+        None => return Ok(()),
+    };
+
+    match (req.method.as_str(), state) {
+        (_, SessionState::Closed) => Err(McpError::SessionNotFound(
+            session.map(|s| s.id.clone()).unwrap_or_default(),
+        )),
+        ("initialize", SessionState::Uninitialized) => Ok(()),
+        ("initialize", _) => Err(McpError::InvalidRequest(
+            "Session already initialized".to_string(),
+        )),
+        ("ping", _) => Ok(()),
+        ("notifications/initialized", SessionState::Initializing) => Ok(()),
+        (_, SessionState::Ready) => Ok(()),
+        ("notifications/initialized", _) => Err(McpError::NotInitialized),
+        _ if req.is_notification() => Ok(()),
+        _ => Err(McpError::NotInitialized),
+    }
+}
+
+/// Determine the protocol revision a request is made under
+///
+/// The `MCP-Protocol-Version` header must name a supported revision and, for
+/// session requests, match the revision negotiated at initialization.
+fn request_protocol_version(
+    ctx: &RequestContext,
+    session: Option<&Session>,
+) -> Result<ProtocolVersion> {
+    let header = match ctx.get_protocol_version() {
+        Some(value) => Some(ProtocolVersion::parse(&value).ok_or_else(|| {
+            McpError::InvalidRequest(format!("Unsupported protocol version: {}", value))
+        })?),
+        None => None,
+    };
+
+    match (header, session) {
+        (Some(header), Some(session)) if header != session.protocol_version => {
+            Err(McpError::InvalidRequest(format!(
+                "Protocol version {} does not match negotiated version {}",
+                header, session.protocol_version
+            )))
+        }
+        (Some(header), _) => Ok(header),
+        (None, Some(session)) => Ok(session.protocol_version),
+        (None, None) => Ok(ProtocolVersion::ASSUMED),
+    }
+}
+
 fn create_request_context(req: &HttpRequest) -> RequestContext {
-    RequestContext::new(
+    let mut ctx = RequestContext::new(
         req.headers().clone(),
         req.method().to_string(),
         req.path().to_string(),
         req.peer_addr(),
-    )
+    );
+    ctx.peer_certificate = req.conn_data::<PeerCertificate>().cloned();
+    ctx
 }
@@ -0,0 +1,195 @@
+use serde_json::Value;
+use std::fmt;
+
+/// MCP protocol revisions implemented by this library, newest first
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[ProtocolVersion] = &[
+    ProtocolVersion::V2025_06_18,
+    ProtocolVersion::V2025_03_26,
+    ProtocolVersion::V2024_11_05,
+];
+
+/// Header carrying the negotiated protocol version on requests after initialization
+pub const PROTOCOL_VERSION_HEADER: &str = "mcp-protocol-version";
+
+/// An MCP protocol revision
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtocolVersion {
+    V2024_11_05,
+    V2025_03_26,
+    V2025_06_18,
+}
+
+impl ProtocolVersion {
+    /// Newest revision supported by this library
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V2025_06_18;
+
+    /// Revision assumed when a request carries no `MCP-Protocol-Version` header
+    /// and no session says otherwise, as required by the Streamable HTTP spec
+    pub const ASSUMED: ProtocolVersion = ProtocolVersion::V2025_03_26;
+
+    /// Parse a revision string, returning `None` for unsupported revisions
+    pub fn parse(version: &str) -> Option<Self> {
+        SUPPORTED_PROTOCOL_VERSIONS
+            .iter()
+            .copied()
+            .find(|v| v.as_str() == version)
+    }
+
+    /// Pick the revision to answer an `initialize` request with
+    ///
+    /// The client's revision is echoed if supported, otherwise the latest
+    /// supported revision is proposed and the client decides whether to continue.
+    pub fn negotiate(requested: &str) -> Self {
+        Self::parse(requested).unwrap_or(Self::LATEST)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProtocolVersion::V2024_11_05 => "2024-11-05",
+            ProtocolVersion::V2025_03_26 => "2025-03-26",
+            ProtocolVersion::V2025_06_18 => "2025-06-18",
+        }
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Remove fields from a result that the negotiated revision does not define
+///
+/// Results are built against the latest revision; older clients get the same
+/// data without the newer fields and content types.
+pub(crate) fn adapt_result(method: &str, result: &mut Value, version: ProtocolVersion) {
+    if version >= ProtocolVersion::LATEST {
+        return;
+    }
+
+    match method {
+        "tools/list" => {
+            for tool in array_mut(result, "tools") {
+                if let Some(tool) = tool.as_object_mut() {
+                    tool.remove("title");
+                    tool.remove("outputSchema");
+                    if version < ProtocolVersion::V2025_03_26 {
+                        tool.remove("annotations");
+                    }
+                }
+            }
+        }
+        "prompts/list" => remove_titles(array_mut(result, "prompts")),
+        "resources/list" => remove_titles(array_mut(result, "resources")),
+        "resources/templates/list" => remove_titles(array_mut(result, "resourceTemplates")),
+        "tools/call" => {
+            for content in array_mut(result, "content") {
+                downgrade_content(content, version);
+            }
+        }
+        "prompts/get" => {
+            for message in array_mut(result, "messages") {
+                if let Some(content) = message.get_mut("content") {
+                    downgrade_content(content, version);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn array_mut<'a>(value: &'a mut Value, key: &str) -> impl Iterator<Item = &'a mut Value> {
+    value
+        .get_mut(key)
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+}
+
+fn remove_titles<'a>(items: impl Iterator<Item = &'a mut Value>) {
+    for item in items {
+        if let Some(item) = item.as_object_mut() {
+            item.remove("title");
+        }
+    }
+}
+
+/// Rewrite a content block into a form the given revision understands
+fn downgrade_content(content: &mut Value, version: ProtocolVersion) {
+    if let Some(annotations) = content
+        .get_mut("annotations")
+        .and_then(Value::as_object_mut)
+    {
+        annotations.remove("lastModified");
+    }
+
+    let replacement = match content.get("type").and_then(Value::as_str) {
+        Some("resource_link") => {
+            let uri = content.get("uri").and_then(Value::as_str).unwrap_or("");
+            Some(format!("Resource: {}", uri))
+        }
+        Some("audio") if version < ProtocolVersion::V2025_03_26 => {
+            let mime = content
+                .get("mimeType")
+                .and_then(Value::as_str)
+                .unwrap_or("");
+            Some(format!(
+                "[audio content ({}) not supported by client]",
+                mime
+            ))
+        }
+        _ => None,
+    };
+
+    if let Some(text) = replacement {
+        *content = serde_json::json!({ "type": "text", "text": text });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(
+            ProtocolVersion::negotiate("2024-11-05"),
+            ProtocolVersion::V2024_11_05
+        );
+        assert_eq!(
+            ProtocolVersion::negotiate("2025-99-99"),
+            ProtocolVersion::LATEST
+        );
+        assert_eq!(ProtocolVersion::parse("2025-99-99"), None);
+    }
+
+    #[test]
+    fn test_adapt_result_for_old_revisions() {
+        let mut tools = json!({
+            "tools": [{
+                "name": "rm",
+                "title": "Remove",
+                "inputSchema": {"type": "object"},
+                "annotations": {"destructiveHint": true}
+            }]
+        });
+        adapt_result("tools/list", &mut tools, ProtocolVersion::V2025_03_26);
+        assert!(tools["tools"][0].get("title").is_none());
+        assert!(tools["tools"][0].get("annotations").is_some());
+
+        adapt_result("tools/list", &mut tools, ProtocolVersion::V2024_11_05);
+        assert!(tools["tools"][0].get("annotations").is_none());
+
+        let mut call = json!({
+            "content": [
+                {"type": "resource_link", "uri": "file:///a", "name": "a"},
+                {"type": "audio", "data": "AAAA", "mimeType": "audio/wav"}
+            ]
+        });
+        adapt_result("tools/call", &mut call, ProtocolVersion::V2024_11_05);
+        assert_eq!(call["content"][0]["type"], "text");
+        assert_eq!(call["content"][0]["text"], "Resource: file:///a");
+        assert_eq!(call["content"][1]["type"], "text");
+    }
+}
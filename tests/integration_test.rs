@@ -1,5 +1,11 @@
-use httpmcp_rust::protocol::{Resource, ResourceContents};
-use httpmcp_rust::{HttpMcpServer, RequestContext, ResourceMeta, Result, ToolMeta};
+use httpmcp_rust::handlers::{ResourceProvider, ToolProvider};
+use httpmcp_rust::protocol::{LogLevel, Resource, ResourceContents};
+use httpmcp_rust::transport::create_app;
+use httpmcp_rust::{
+    CorsConfig, EndpointMeta, ErrorStatusPolicy, HttpMcpServer, IntoToolResult, McpError,
+    ProtocolVersion, RequestContext, ResourceMeta, Result, SessionCloseReason, Tool, ToolContent,
+    ToolMeta, ToolsCallResult,
+};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 
@@ -94,13 +100,13 @@ fn test_metadata_builders() {
 async fn test_resource_handlers() {
     use actix_web::http::header::HeaderMap;
 
-    let ctx = RequestContext {
-        request_id: "test-123".to_string(),
-        method: "POST".to_string(),
-        path: "/mcp".to_string(),
-        remote_addr: None,
-        headers: HeaderMap::new(),
-    };
+    let mut ctx = RequestContext::new(
+        HeaderMap::new(),
+        "POST".to_string(),
+        "/mcp".to_string(),
+        None,
+    );
+    ctx.request_id = "test-123".to_string();
 
     // Test list
     let result = test_list_resources(None, ctx.clone()).await;
@@ -123,13 +129,13 @@ async fn test_resource_handlers() {
 async fn test_tool_handler() {
     use actix_web::http::header::HeaderMap;
 
-    let ctx = RequestContext {
-        request_id: "test-123".to_string(),
-        method: "POST".to_string(),
-        path: "/mcp".to_string(),
-        remote_addr: None,
-        headers: HeaderMap::new(),
-    };
+    let mut ctx = RequestContext::new(
+        HeaderMap::new(),
+        "POST".to_string(),
+        "/mcp".to_string(),
+        None,
+    );
+    ctx.request_id = "test-123".to_string();
 
     let mut args = HashMap::new();
     args.insert("message".to_string(), json!("Hello"));
@@ -139,3 +145,2429 @@ async fn test_tool_handler() {
     let value = result.unwrap();
     assert_eq!(value["result"], "Hello");
 }
+
+// Tool handler returning multiple content blocks
+async fn test_image_tool(
+    _args: HashMap<String, Value>,
+    _ctx: RequestContext,
+) -> Result<Vec<ToolContent>> {
+    Ok(vec![
+        ToolContent::text("A tiny image"),
+        ToolContent::image("iVBORw0KGgo=", "image/png"),
+    ])
+}
+
+struct Forecast {
+    high: i32,
+    low: i32,
+}
+
+impl IntoToolResult for Forecast {
+    fn into_tool_result(self) -> Result<ToolsCallResult> {
+        Ok(ToolsCallResult {
+            content: vec![ToolContent::text(format!("{}/{}", self.high, self.low))],
+            is_error: Some(self.low > self.high),
+        })
+    }
+}
+
+#[actix_web::test]
+async fn test_tool_result_conversions() {
+    let result = json!({"ok": true}).into_tool_result().unwrap();
+    assert!(
+        matches!(&result.content[0], ToolContent::Text { text, .. } if text == r#"{"ok":true}"#)
+    );
+    assert!(result.is_error.is_none());
+
+    let result = (vec![ToolContent::text("failed")], true)
+        .into_tool_result()
+        .unwrap();
+    assert_eq!(result.is_error, Some(true));
+
+    let result = Forecast { high: 20, low: 12 }.into_tool_result().unwrap();
+    assert_eq!(result.is_error, Some(false));
+
+    let server = HttpMcpServer::builder()
+        .require_sessions(false)
+        .tool("json", ToolMeta::new(), test_tool)
+        .tool("image", ToolMeta::new(), test_image_tool)
+        .tool("text", ToolMeta::new(), |_args, _ctx| async {
+            Ok("plain text".to_string())
+        })
+        .tool("forecast", ToolMeta::new(), |_args, _ctx| async {
+            Ok(Forecast { high: 20, low: 12 })
+        })
+        .tool("flagged", ToolMeta::new(), |_args, _ctx| async {
+            Ok((vec![ToolContent::text("disk full")], true))
+        })
+        .tool("failing", ToolMeta::new(), |_args, _ctx| async {
+            Err::<String, _>(McpError::InvalidParams("missing city".to_string()))
+        })
+        .build()
+        .unwrap();
+    let call = |name: &str| {
+        let request = httpmcp_rust::jsonrpc::JsonRpcRequest::new(
+            "tools/call",
+            Some(json!({"name": name, "arguments": {"message": "hi"}})),
+            Some(httpmcp_rust::jsonrpc::RequestId::Number(1)),
+        );
+        let ctx = RequestContext::new(
+            actix_web::http::header::HeaderMap::new(),
+            "POST".to_string(),
+            "/mcp".to_string(),
+            None,
+        );
+        let server = &server;
+        async move {
+            let response = server.handle_message(request, ctx).await.unwrap();
+            serde_json::to_value(response).unwrap()
+        }
+    };
+
+    let text = call("text").await;
+    assert_eq!(
+        text["result"],
+        json!({"content": [{"type": "text", "text": "plain text"}]})
+    );
+
+    let image = call("image").await;
+    assert_eq!(
+        image["result"],
+        json!({"content": [
+            {"type": "text", "text": "A tiny image"},
+            {"type": "image", "data": "iVBORw0KGgo=", "mimeType": "image/png"}
+        ]})
+    );
+
+    let forecast = call("forecast").await;
+    assert_eq!(
+        forecast["result"],
+        json!({"content": [{"type": "text", "text": "20/12"}], "isError": false})
+    );
+
+    let flagged = call("flagged").await;
+    assert_eq!(
+        flagged["result"],
+        json!({"content": [{"type": "text", "text": "disk full"}], "isError": true})
+    );
+
+    let json_result = call("json").await;
+    assert_eq!(json_result["result"]["content"][0]["type"], "text");
+    assert!(json_result["result"].get("isError").is_none());
+
+    // Handler errors are JSON-RPC errors, not tool results
+    let failing = call("failing").await;
+    assert!(failing.get("result").is_none());
+    assert_eq!(failing["error"]["code"], -32602);
+    assert_eq!(failing["error"]["message"], "missing city");
+}
+
+struct InventoryProvider;
+
+#[async_trait::async_trait]
+impl ToolProvider for InventoryProvider {
+    async fn list_tools(&self, _ctx: &RequestContext) -> Result<Vec<Tool>> {
+        Ok(vec![ToolMeta::new()
+            .description("Count items in stock")
+            .to_tool("count_items")])
+    }
+
+    async fn call_tool(
+        &self,
+        name: &str,
+        _arguments: Option<HashMap<String, Value>>,
+        _ctx: &RequestContext,
+    ) -> Result<(Vec<ToolContent>, bool)> {
+        assert_eq!(name, "count_items");
+        Ok((vec![ToolContent::text("42")], false))
+    }
+}
+
+fn mcp_request(body: Value) -> actix_web::test::TestRequest {
+    actix_web::test::TestRequest::post()
+        .uri("/mcp")
+        .insert_header(("content-type", "application/json"))
+        .insert_header(("accept", "application/json, text/event-stream"))
+        .set_json(body)
+}
+
+/// Initialize a session against a test service and return its ID
+macro_rules! initialize_session {
+    ($app:expr) => {{
+        let resp = actix_web::test::call_service(
+            $app,
+            mcp_request(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": {},
+                    "clientInfo": {"name": "test-client", "version": "1.0"}
+                }
+            }))
+            .to_request(),
+        )
+        .await;
+        let session_id = resp
+            .headers()
+            .get("mcp-session-id")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        let resp = actix_web::test::call_service(
+            $app,
+            mcp_request(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+                .insert_header(("mcp-session-id", session_id.as_str()))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::ACCEPTED);
+        session_id
+    }};
+}
+
+#[actix_web::test]
+async fn test_tool_provider_coexists_with_functions() {
+    let server = HttpMcpServer::builder()
+        .require_sessions(false)
+        .tool("test_tool", ToolMeta::new(), test_tool)
+        .tool_provider(InventoryProvider)
+        .sse_responses(false)
+        .build()
+        .unwrap();
+    let server = std::sync::Arc::new(server);
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+
+    let list: Value = actix_web::test::call_and_read_body_json(
+        &app,
+        mcp_request(json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"})).to_request(),
+    )
+    .await;
+    let mut names: Vec<&str> = list["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["count_items", "test_tool"]);
+
+    let call: Value = actix_web::test::call_and_read_body_json(
+        &app,
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "count_items"}
+        }))
+        .to_request(),
+    )
+    .await;
+    assert_eq!(call["result"]["content"][0]["text"], "42");
+    assert_eq!(call["result"]["isError"], false);
+}
+
+/// Lists `count` resources under `scheme://` two at a time
+struct PagedResources {
+    scheme: &'static str,
+    count: usize,
+    subscribe: bool,
+}
+
+#[async_trait::async_trait]
+impl ResourceProvider for PagedResources {
+    async fn list_resources(
+        &self,
+        cursor: Option<&str>,
+        _ctx: &RequestContext,
+    ) -> Result<(Vec<Resource>, Option<String>)> {
+        let start: usize = cursor.map_or(0, |c| c.parse().unwrap());
+        let end = (start + 2).min(self.count);
+        let resources = (start..end)
+            .map(|i| Resource {
+                uri: format!("{}://{}", self.scheme, i),
+                name: format!("{} {}", self.scheme, i),
+                description: None,
+                mime_type: None,
+            })
+            .collect();
+        Ok((resources, (end < self.count).then(|| end.to_string())))
+    }
+
+    async fn read_resource(
+        &self,
+        uri: &str,
+        _ctx: &RequestContext,
+    ) -> Result<Vec<ResourceContents>> {
+        Err(McpError::ResourceNotFound(uri.to_string()))
+    }
+
+    fn supports_subscribe(&self) -> bool {
+        self.subscribe
+    }
+
+    async fn subscribe(&self, uri: &str, _ctx: &RequestContext) -> Result<()> {
+        match uri.starts_with(&format!("{}://", self.scheme)) {
+            true => Ok(()),
+            false => Err(McpError::ResourceNotFound(uri.to_string())),
+        }
+    }
+}
+
+#[actix_web::test]
+async fn test_resource_pages_and_subscriptions() {
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .require_sessions(false)
+            .resource(
+                "test://resource",
+                ResourceMeta::new(),
+                test_list_resources,
+                test_read_resource,
+            )
+            .resource_provider(PagedResources {
+                scheme: "a",
+                count: 3,
+                subscribe: false,
+            })
+            .resource_provider(PagedResources {
+                scheme: "b",
+                count: 2,
+                subscribe: true,
+            })
+            .sse_responses(false)
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+    let call = |method: &str, params: Value| {
+        mcp_request(json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}))
+            .to_request()
+    };
+
+    // Every source is reachable by following the cursors
+    let mut uris = Vec::new();
+    let mut pages = 0;
+    let mut params = json!({});
+    loop {
+        let body: Value =
+            actix_web::test::call_and_read_body_json(&app, call("resources/list", params)).await;
+        pages += 1;
+        for resource in body["result"]["resources"].as_array().unwrap() {
+            uris.push(resource["uri"].as_str().unwrap().to_string());
+        }
+        match body["result"]["nextCursor"].as_str() {
+            Some(cursor) => params = json!({"cursor": cursor}),
+            None => break,
+        }
+    }
+    assert_eq!(
+        uris,
+        [
+            "test://resource",
+            "a://0",
+            "a://1",
+            "a://2",
+            "b://0",
+            "b://1"
+        ]
+    );
+    assert_eq!(pages, 2);
+
+    let body: Value = actix_web::test::call_and_read_body_json(
+        &app,
+        call("resources/list", json!({"cursor": "not-a-cursor"})),
+    )
+    .await;
+    assert_eq!(body["error"]["code"], -32602);
+    let body: Value =
+        actix_web::test::call_and_read_body_json(&app, call("prompts/list", json!({"cursor": 5})))
+            .await;
+    assert_eq!(body["error"]["code"], -32602);
+
+    // Only providers that support subscriptions accept them, and only for their URIs
+    let body: Value = actix_web::test::call_and_read_body_json(
+        &app,
+        call(
+            "initialize",
+            json!({
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": {"name": "test-client", "version": "1.0"}
+            }),
+        ),
+    )
+    .await;
+    assert_eq!(
+        body["result"]["capabilities"]["resources"]["subscribe"],
+        true
+    );
+    let body: Value = actix_web::test::call_and_read_body_json(
+        &app,
+        call("resources/subscribe", json!({"uri": "b://1"})),
+    )
+    .await;
+    assert!(body["result"].is_object());
+    for uri in ["a://1", "test://resource", "unknown://x"] {
+        let body: Value = actix_web::test::call_and_read_body_json(
+            &app,
+            call("resources/subscribe", json!({"uri": uri})),
+        )
+        .await;
+        assert!(
+            body["error"].is_object(),
+            "{} should not be subscribable",
+            uri
+        );
+    }
+
+    let server = HttpMcpServer::builder()
+        .resource_provider(PagedResources {
+            scheme: "a",
+            count: 1,
+            subscribe: false,
+        })
+        .build()
+        .unwrap();
+    let initialize = httpmcp_rust::jsonrpc::JsonRpcRequest::new(
+        "initialize",
+        Some(json!({
+            "protocolVersion": "2025-06-18",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0"}
+        })),
+        Some(httpmcp_rust::jsonrpc::RequestId::Number(1)),
+    );
+    let ctx = RequestContext::new(
+        actix_web::http::header::HeaderMap::new(),
+        "POST".to_string(),
+        "/mcp".to_string(),
+        None,
+    );
+    let response = server.handle_message(initialize, ctx).await.unwrap();
+    let capabilities = &response.result.unwrap()["capabilities"];
+    assert!(capabilities["resources"].is_object());
+    assert!(capabilities["resources"].get("subscribe").is_none());
+}
+
+#[actix_web::test]
+async fn test_protocol_version_negotiation() {
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .tool("test_tool", ToolMeta::new().title("Test"), test_tool)
+            .sse_responses(false)
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+
+    let initialize = |version: &str| {
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": version,
+                "capabilities": {},
+                "clientInfo": {"name": "test-client", "version": "1.0"}
+            }
+        }))
+        .to_request()
+    };
+
+    // Unknown versions are answered with the latest supported one
+    let resp = actix_web::test::call_service(&app, initialize("2099-01-01")).await;
+    let body: Value = actix_web::test::read_body_json(resp).await;
+    assert_eq!(
+        body["result"]["protocolVersion"],
+        ProtocolVersion::LATEST.as_str()
+    );
+
+    // Supported versions are echoed and stored on the session
+    let resp = actix_web::test::call_service(&app, initialize("2024-11-05")).await;
+    let session_id = resp
+        .headers()
+        .get("mcp-session-id")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body: Value = actix_web::test::read_body_json(resp).await;
+    assert_eq!(body["result"]["protocolVersion"], "2024-11-05");
+    assert_eq!(
+        server.session(&session_id).unwrap().protocol_version,
+        ProtocolVersion::V2024_11_05
+    );
+
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .insert_header(("mcp-session-id", session_id.as_str()))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::ACCEPTED);
+
+    // Results are shaped for the negotiated revision
+    let list: Value = actix_web::test::call_and_read_body_json(
+        &app,
+        mcp_request(json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}))
+            .insert_header(("mcp-session-id", session_id.as_str()))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(list["result"]["tools"][0]["name"], "test_tool");
+    assert!(list["result"]["tools"][0].get("title").is_none());
+
+    // A header that disagrees with the session is rejected
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({"jsonrpc": "2.0", "id": 3, "method": "tools/list"}))
+            .insert_header(("mcp-session-id", session_id.as_str()))
+            .insert_header(("mcp-protocol-version", "2025-06-18"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+    // Unknown sessions get 404 so the client re-initializes
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({"jsonrpc": "2.0", "id": 4, "method": "tools/list"}))
+            .insert_header(("mcp-session-id", "expired"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_session_lifecycle() {
+    let initialized = std::sync::Arc::new(std::sync::Mutex::new(None));
+    let seen = initialized.clone();
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .tool("test_tool", ToolMeta::new(), test_tool)
+            .require_sessions(true)
+            .sse_responses(false)
+            .on_initialized(move |client_info, _ctx| {
+                let seen = seen.clone();
+                async move {
+                    *seen.lock().unwrap() = Some(client_info.name);
+                    Ok(())
+                }
+            })
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+
+    let tools_list = || mcp_request(json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}));
+
+    // Requests before initialization are rejected when sessions are required
+    let resp = actix_web::test::call_service(&app, tools_list().to_request()).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": {"name": "lifecycle-client", "version": "1.0"}
+            }
+        }))
+        .to_request(),
+    )
+    .await;
+    let session_id = resp
+        .headers()
+        .get("mcp-session-id")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    // Still initializing: only ping is allowed
+    let resp = actix_web::test::call_service(
+        &app,
+        tools_list()
+            .insert_header(("mcp-session-id", session_id.as_str()))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+    let ping: Value = actix_web::test::call_and_read_body_json(
+        &app,
+        mcp_request(json!({"jsonrpc": "2.0", "id": 3, "method": "ping"}))
+            .insert_header(("mcp-session-id", session_id.as_str()))
+            .to_request(),
+    )
+    .await;
+    assert!(ping["result"].is_object());
+
+    // Other notifications are accepted while initializing
+    for notification in [
+        json!({"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": 3}}),
+        json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/progress",
+            "params": {"progressToken": "t", "progress": 1}
+        }),
+    ] {
+        let resp = actix_web::test::call_service(
+            &app,
+            mcp_request(notification)
+                .insert_header(("mcp-session-id", session_id.as_str()))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::ACCEPTED);
+    }
+    assert!(initialized.lock().unwrap().is_none());
+
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .insert_header(("mcp-session-id", session_id.as_str()))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::ACCEPTED);
+    assert_eq!(
+        initialized.lock().unwrap().as_deref(),
+        Some("lifecycle-client")
+    );
+
+    let list: Value = actix_web::test::call_and_read_body_json(
+        &app,
+        tools_list()
+            .insert_header(("mcp-session-id", session_id.as_str()))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(list["result"]["tools"][0]["name"], "test_tool");
+
+    // A second initialize in the same session is rejected
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": 4,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": {"name": "lifecycle-client", "version": "1.0"}
+            }
+        }))
+        .insert_header(("mcp-session-id", session_id.as_str()))
+        .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_sessionless_requests() {
+    let call = || {
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "test_tool", "arguments": {"message": "hi"}}
+        }))
+        .to_request()
+    };
+    let initialize = || {
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": {"name": "test-client", "version": "1.0"}
+            }
+        }))
+        .to_request()
+    };
+
+    // Servers issue sessions by default, so tools wait for initialization
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .tool("test_tool", ToolMeta::new(), test_tool)
+            .sse_responses(false)
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+    let resp = actix_web::test::call_service(&app, call()).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+    // A stateless server serves every request and issues no sessions
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .tool("test_tool", ToolMeta::new(), test_tool)
+            .require_sessions(false)
+            .sse_responses(false)
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+    let resp = actix_web::test::call_service(&app, initialize()).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+    assert!(resp.headers().get("mcp-session-id").is_none());
+    let resp = actix_web::test::call_service(&app, call()).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+    let body: Value = actix_web::test::read_body_json(resp).await;
+    assert!(body["result"].is_object());
+}
+
+#[actix_web::test]
+async fn test_session_termination_and_expiry() {
+    let closed = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen = closed.clone();
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .tool("test_tool", ToolMeta::new(), test_tool)
+            .session_idle_timeout(std::time::Duration::from_millis(50))
+            .sse_responses(false)
+            .on_session_closed(move |session, reason| {
+                let seen = seen.clone();
+                async move {
+                    seen.lock().unwrap().push((session.id, reason));
+                    Ok(())
+                }
+            })
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+
+    let delete = |session_id: &str| {
+        actix_web::test::TestRequest::delete()
+            .uri("/mcp")
+            .insert_header(("mcp-session-id", session_id))
+            .to_request()
+    };
+    let ping = |session_id: &str| {
+        mcp_request(json!({"jsonrpc": "2.0", "id": 2, "method": "ping"}))
+            .insert_header(("mcp-session-id", session_id))
+            .to_request()
+    };
+
+    // DELETE terminates the session
+    let session_id = initialize_session!(&app);
+    let resp = actix_web::test::call_service(&app, delete(&session_id)).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NO_CONTENT);
+    assert!(server.session(&session_id).is_none());
+    assert_eq!(
+        closed.lock().unwrap().last(),
+        Some(&(session_id.clone(), SessionCloseReason::Terminated))
+    );
+
+    let resp = actix_web::test::call_service(&app, ping(&session_id)).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    let resp = actix_web::test::call_service(&app, delete(&session_id)).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+    // Idle sessions expire
+    let session_id = initialize_session!(&app);
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    let resp = actix_web::test::call_service(&app, ping(&session_id)).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    assert_eq!(
+        closed.lock().unwrap().last(),
+        Some(&(session_id, SessionCloseReason::IdleTimeout))
+    );
+
+    // Mounting the routes starts the reaper, so abandoned sessions close on their own
+    let session_id = initialize_session!(&app);
+    tokio::time::sleep(std::time::Duration::from_millis(1200)).await;
+    assert!(server.session(&session_id).is_none());
+    assert_eq!(
+        closed.lock().unwrap().last(),
+        Some(&(session_id, SessionCloseReason::IdleTimeout))
+    );
+}
+
+#[actix_web::test]
+async fn test_read_only_sessions() {
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .tool(
+                "delete_file",
+                ToolMeta::new().destructive(true),
+                |_args, _ctx| async { Ok("deleted") },
+            )
+            .session_attributes(|_params, ctx| {
+                let read_only = ctx.get_bearer_token().as_deref() == Some("viewer-token");
+                Ok(HashMap::from([("read_only".to_string(), json!(read_only))]))
+            })
+            .tool_policy(|tool, ctx| {
+                if ctx.session_attribute("read_only") == Some(&json!(true)) && tool.is_destructive()
+                {
+                    return Err(McpError::AuthorizationFailed(format!(
+                        "{} is destructive",
+                        tool.name
+                    )));
+                }
+                Ok(())
+            })
+            .sse_responses(false)
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+
+    let initialize = |token: &str| {
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": {"name": "test-client", "version": "1.0"}
+            }
+        }))
+        .insert_header(("authorization", format!("Bearer {}", token)))
+        .to_request()
+    };
+    let resp = actix_web::test::call_service(&app, initialize("viewer-token")).await;
+    let viewer = resp
+        .headers()
+        .get("mcp-session-id")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    let resp = actix_web::test::call_service(&app, initialize("editor-token")).await;
+    let editor = resp
+        .headers()
+        .get("mcp-session-id")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    for session_id in [&viewer, &editor] {
+        actix_web::test::call_service(
+            &app,
+            mcp_request(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+                .insert_header(("mcp-session-id", session_id.as_str()))
+                .to_request(),
+        )
+        .await;
+    }
+    assert_eq!(
+        server.session(&viewer).unwrap().attributes["read_only"],
+        json!(true)
+    );
+
+    // The attribute follows the session, whatever later requests carry
+    let call = |session_id: &str| {
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "delete_file", "arguments": {}}
+        }))
+        .insert_header(("mcp-session-id", session_id))
+        .to_request()
+    };
+    let body: Value = actix_web::test::call_and_read_body_json(&app, call(&viewer)).await;
+    assert_eq!(
+        body["error"]["message"],
+        "Authorization failed: delete_file is destructive"
+    );
+    let body: Value = actix_web::test::call_and_read_body_json(&app, call(&editor)).await;
+    assert_eq!(body["result"]["content"][0]["text"], "deleted");
+
+    // Attributes can change for the rest of the session
+    assert!(server.set_session_attribute(&viewer, "read_only", json!(false)));
+    let body: Value = actix_web::test::call_and_read_body_json(&app, call(&viewer)).await;
+    assert_eq!(body["result"]["content"][0]["text"], "deleted");
+    assert!(!server.set_session_attribute("unknown", "read_only", json!(false)));
+}
+
+#[actix_web::test]
+async fn test_batches_before_2025_06_18() {
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .tool("test_tool", ToolMeta::new(), test_tool)
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-03-26",
+                "capabilities": {},
+                "clientInfo": {"name": "test-client", "version": "1.0"}
+            }
+        }))
+        .to_request(),
+    )
+    .await;
+    let session_id = resp
+        .headers()
+        .get("mcp-session-id")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    let batch = |session_id: &str, messages: Value| {
+        mcp_request(messages)
+            .insert_header(("mcp-session-id", session_id))
+            .to_request()
+    };
+
+    // A batch of notifications is accepted without a body
+    let resp = actix_web::test::call_service(
+        &app,
+        batch(
+            &session_id,
+            json!([{"jsonrpc": "2.0", "method": "notifications/initialized"}]),
+        ),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::ACCEPTED);
+
+    // Requests are answered in order; notifications get no response
+    let body: Value = actix_web::test::call_and_read_body_json(
+        &app,
+        batch(
+            &session_id,
+            json!([
+                {"jsonrpc": "2.0", "id": 2, "method": "ping"},
+                {"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": 9}},
+                {"jsonrpc": "2.0", "id": 3, "method": "tools/list"},
+                {"jsonrpc": "2.0", "id": 4},
+                {"jsonrpc": "2.0", "id": 5, "method": "initialize", "params": {}}
+            ]),
+        ),
+    )
+    .await;
+    let responses = body.as_array().unwrap();
+    assert_eq!(responses.len(), 4);
+    assert_eq!(responses[0]["id"], 2);
+    assert_eq!(responses[0]["result"], json!({}));
+    assert_eq!(responses[1]["id"], 3);
+    assert_eq!(responses[1]["result"]["tools"][0]["name"], "test_tool");
+    assert_eq!(responses[2]["id"], 4);
+    assert_eq!(responses[2]["error"]["code"], -32600);
+    assert_eq!(responses[3]["id"], 5);
+    assert_eq!(responses[3]["error"]["code"], -32600);
+
+    let resp = actix_web::test::call_service(&app, batch(&session_id, json!([]))).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+    // 2025-06-18 removed batching
+    let session_id = initialize_session!(&app);
+    let resp = actix_web::test::call_service(
+        &app,
+        batch(
+            &session_id,
+            json!([{"jsonrpc": "2.0", "id": 2, "method": "ping"}]),
+        ),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    let body: Value = actix_web::test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], -32600);
+}
+
+#[actix_web::test]
+async fn test_post_streams_notifications_and_result() {
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .require_sessions(false)
+            .tool(
+                "slow_tool",
+                ToolMeta::new(),
+                |_args, ctx: RequestContext| async move {
+                    ctx.log(LogLevel::Info, "starting");
+                    ctx.notify_progress(0.5, Some(1.0), Some("halfway"));
+                    Ok("done".to_string())
+                },
+            )
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "tools/call",
+            "params": {"name": "slow_tool", "_meta": {"progressToken": "p1"}}
+        }))
+        .insert_header(("accept", "application/json, text/event-stream"))
+        .to_request(),
+    )
+    .await;
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/event-stream"
+    );
+
+    let body = actix_web::test::read_body(resp).await;
+    let messages: Vec<Value> = std::str::from_utf8(&body)
+        .unwrap()
+        .lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .map(|data| serde_json::from_str(data).unwrap())
+        .collect();
+
+    assert_eq!(messages.len(), 3);
+    assert_eq!(messages[0]["method"], "notifications/message");
+    assert_eq!(messages[0]["params"]["data"], "starting");
+    assert_eq!(messages[1]["method"], "notifications/progress");
+    assert_eq!(messages[1]["params"]["progressToken"], "p1");
+    assert_eq!(messages[2]["id"], 7);
+    assert_eq!(messages[2]["result"]["content"][0]["text"], "done");
+}
+
+#[actix_web::test]
+async fn test_errors_carry_request_id() {
+    let call_missing_tool = |id: i64| {
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": {"name": "missing"}
+        }))
+        .to_request()
+    };
+
+    // Handler errors are JSON-RPC errors answered with 200 by default
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .require_sessions(false)
+            .tool("test_tool", ToolMeta::new(), test_tool)
+            .sse_responses(false)
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+
+    let resp = actix_web::test::call_service(&app, call_missing_tool(7)).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+    let body: Value = actix_web::test::read_body_json(resp).await;
+    assert_eq!(body["id"], 7);
+    assert_eq!(body["error"]["code"], -32601);
+
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({"jsonrpc": "1.0", "id": "abc", "method": "ping"})).to_request(),
+    )
+    .await;
+    let body: Value = actix_web::test::read_body_json(resp).await;
+    assert_eq!(body["id"], "abc");
+    assert_eq!(body["error"]["code"], -32600);
+
+    // Transport errors keep their HTTP status and still name the request
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({"jsonrpc": "2.0", "id": 8, "method": "tools/list"}))
+            .insert_header(("mcp-session-id", "unknown"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    let body: Value = actix_web::test::read_body_json(resp).await;
+    assert_eq!(body["id"], 8);
+
+    // The status policy can map error codes to HTTP statuses
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .require_sessions(false)
+            .error_status(ErrorStatusPolicy::FromErrorCode)
+            .sse_responses(false)
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+    let resp = actix_web::test::call_service(&app, call_missing_tool(9)).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    let body: Value = actix_web::test::read_body_json(resp).await;
+    assert_eq!(body["id"], 9);
+}
+
+#[actix_web::test]
+async fn test_malformed_bodies_get_jsonrpc_errors() {
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .max_payload_size(256)
+            .sse_responses(false)
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+    let raw = |body: &'static str| {
+        actix_web::test::TestRequest::post()
+            .uri("/mcp")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("accept", "application/json, text/event-stream"))
+            .set_payload(body)
+            .to_request()
+    };
+
+    let resp = actix_web::test::call_service(&app, raw("{\"jsonrpc\": \"2.0\",")).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+    let body: Value = actix_web::test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], -32700);
+    assert!(body["id"].is_null());
+
+    let body: Value =
+        actix_web::test::call_and_read_body_json(&app, raw(r#"{"jsonrpc": "2.0", "id": 4}"#)).await;
+    assert_eq!(body["error"]["code"], -32600);
+    assert_eq!(body["id"], 4);
+
+    // Each invalid member of a batch gets its own error
+    let body: Value = actix_web::test::call_and_read_body_json(&app, raw("[1, 2]")).await;
+    assert_eq!(body.as_array().unwrap().len(), 2);
+    assert_eq!(body[0]["error"]["code"], -32600);
+    assert_eq!(body[1]["error"]["code"], -32600);
+
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": 5,
+            "method": "ping",
+            "params": {"padding": "x".repeat(512)}
+        }))
+        .to_request(),
+    )
+    .await;
+    assert_eq!(
+        resp.status(),
+        actix_web::http::StatusCode::PAYLOAD_TOO_LARGE
+    );
+    let body: Value = actix_web::test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], -32600);
+}
+
+#[actix_web::test]
+async fn test_content_negotiation() {
+    let ping = json!({"jsonrpc": "2.0", "id": 1, "method": "ping"});
+    let strict = std::sync::Arc::new(HttpMcpServer::builder().build().unwrap());
+    let lenient = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .lenient_content_negotiation(true)
+            .build()
+            .unwrap(),
+    );
+    let strict_app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, strict.clone())),
+    )
+    .await;
+    let lenient_app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, lenient.clone())),
+    )
+    .await;
+
+    // POST clients must accept both JSON and SSE responses
+    let json_only = || {
+        actix_web::test::TestRequest::post()
+            .uri("/mcp")
+            .insert_header(("accept", "application/json"))
+            .set_json(ping.clone())
+            .to_request()
+    };
+    let resp = actix_web::test::call_service(&strict_app, json_only()).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_ACCEPTABLE);
+    let resp = actix_web::test::call_service(&lenient_app, json_only()).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+    let body: Value = actix_web::test::read_body_json(resp).await;
+    assert_eq!(body["id"], 1);
+
+    // POST bodies must be JSON in either mode
+    let text_body = || {
+        actix_web::test::TestRequest::post()
+            .uri("/mcp")
+            .insert_header(("content-type", "text/plain"))
+            .insert_header(("accept", "application/json, text/event-stream"))
+            .set_payload(ping.to_string())
+            .to_request()
+    };
+    let resp = actix_web::test::call_service(&strict_app, text_body()).await;
+    assert_eq!(
+        resp.status(),
+        actix_web::http::StatusCode::UNSUPPORTED_MEDIA_TYPE
+    );
+    let resp = actix_web::test::call_service(&lenient_app, text_body()).await;
+    assert_eq!(
+        resp.status(),
+        actix_web::http::StatusCode::UNSUPPORTED_MEDIA_TYPE
+    );
+
+    // GET must accept an event stream
+    let get = |accept: &'static str| {
+        actix_web::test::TestRequest::get()
+            .uri("/mcp")
+            .insert_header(("accept", accept))
+            .to_request()
+    };
+    let resp = actix_web::test::call_service(&strict_app, get("application/json")).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_ACCEPTABLE);
+    let resp = actix_web::test::call_service(&strict_app, get("text/event-stream")).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+    let resp = actix_web::test::call_service(
+        &lenient_app,
+        actix_web::test::TestRequest::get().uri("/mcp").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_cors_policy() {
+    // Echoing every origin with credentials would let any site act as the user
+    let any_origin = HttpMcpServer::builder()
+        .cors(CorsConfig::new().allow_credentials(true))
+        .build();
+    assert!(matches!(any_origin, Err(McpError::InvalidParams(_))));
+
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .sse_responses(false)
+            .cors(
+                CorsConfig::new()
+                    .allow_origin("https://app.example.com")
+                    .allow_origin_pattern("http://localhost:*")
+                    .allow_credentials(true)
+                    .max_age(std::time::Duration::from_secs(600)),
+            )
+            .endpoint(
+                EndpointMeta::new().route("/api/health").method("GET"),
+                |_ctx, _body| async { Ok(actix_web::HttpResponse::Ok().body("ok")) },
+            )
+            .endpoint(
+                EndpointMeta::new().route("/api/health").method("POST"),
+                |_ctx, _body| async { Ok(actix_web::HttpResponse::Created().finish()) },
+            )
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+    let preflight = |path: &'static str, origin: &'static str| {
+        actix_web::test::TestRequest::default()
+            .method(actix_web::http::Method::OPTIONS)
+            .uri(path)
+            .insert_header(("origin", origin))
+            .insert_header(("access-control-request-method", "POST"))
+            .insert_header((
+                "access-control-request-headers",
+                "content-type, mcp-session-id",
+            ))
+            .to_request()
+    };
+
+    let resp =
+        actix_web::test::call_service(&app, preflight("/mcp", "http://localhost:5173")).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NO_CONTENT);
+    let headers = resp.headers();
+    assert_eq!(
+        headers.get("access-control-allow-origin").unwrap(),
+        "http://localhost:5173"
+    );
+    assert_eq!(
+        headers.get("access-control-allow-credentials").unwrap(),
+        "true"
+    );
+    assert_eq!(headers.get("access-control-max-age").unwrap(), "600");
+    assert_eq!(
+        headers.get("access-control-allow-headers").unwrap(),
+        "content-type, mcp-session-id"
+    );
+
+    let resp =
+        actix_web::test::call_service(&app, preflight("/mcp", "https://evil.example.com")).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+    assert!(resp.headers().get("access-control-allow-origin").is_none());
+
+    // Custom endpoints share the policy, and both methods on one path still work
+    let resp =
+        actix_web::test::call_service(&app, preflight("/api/health", "https://app.example.com"))
+            .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NO_CONTENT);
+    let resp = actix_web::test::call_service(
+        &app,
+        actix_web::test::TestRequest::post()
+            .uri("/api/health")
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::CREATED);
+
+    // Actual responses carry the origin and expose the session header
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": {"name": "browser", "version": "1.0"}
+            }
+        }))
+        .insert_header(("origin", "https://app.example.com"))
+        .to_request(),
+    )
+    .await;
+    let headers = resp.headers();
+    assert_eq!(
+        headers.get("access-control-allow-origin").unwrap(),
+        "https://app.example.com"
+    );
+    assert_eq!(
+        headers.get("access-control-expose-headers").unwrap(),
+        "Mcp-Session-Id"
+    );
+    assert!(headers.get("mcp-session-id").is_some());
+
+    // Unknown routes are real 404s
+    let resp = actix_web::test::call_service(
+        &app,
+        actix_web::test::TestRequest::get()
+            .uri("/does-not-exist")
+            .insert_header(("origin", "https://app.example.com"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_origin_and_host_validation() {
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .allowed_hosts(&["localhost:*"])
+            .allowed_origins(&["http://localhost:*"])
+            .with_oauth(
+                "id",
+                "secret",
+                "https://auth/token",
+                "https://auth/authorize",
+            )
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+    let ping = |host: &'static str, origin: &'static str| {
+        mcp_request(json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}))
+            .insert_header(("host", host))
+            .insert_header(("origin", origin))
+            .to_request()
+    };
+
+    // Rejected before authentication, which would otherwise answer 401
+    let resp =
+        actix_web::test::call_service(&app, ping("localhost:8080", "http://rebind.example")).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+    let resp =
+        actix_web::test::call_service(&app, ping("rebind.example:8080", "http://localhost:3000"))
+            .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+
+    let resp =
+        actix_web::test::call_service(&app, ping("localhost:8080", "http://localhost:3000")).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+}
+
+/// Read the next chunk of a streaming response body as text
+async fn next_chunk(body: &mut std::pin::Pin<Box<actix_web::body::BoxBody>>) -> String {
+    let chunk =
+        std::future::poll_fn(|cx| actix_web::body::MessageBody::poll_next(body.as_mut(), cx))
+            .await
+            .unwrap()
+            .unwrap();
+    String::from_utf8(chunk.to_vec()).unwrap()
+}
+
+fn sse_data(chunk: &str) -> Value {
+    let data = chunk
+        .lines()
+        .find_map(|line| line.strip_prefix("data: "))
+        .unwrap();
+    serde_json::from_str(data).unwrap()
+}
+
+#[actix_web::test]
+async fn test_legacy_sse_transport() {
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .tool("test_tool", ToolMeta::new(), test_tool)
+            .legacy_sse(true)
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+
+    let resp = actix_web::test::call_service(
+        &app,
+        actix_web::test::TestRequest::get().uri("/sse").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+    let mut body = Box::pin(resp.into_body());
+
+    let endpoint_event = next_chunk(&mut body).await;
+    assert!(endpoint_event.contains("event: endpoint\n"));
+    let endpoint = endpoint_event
+        .lines()
+        .find_map(|line| line.strip_prefix("data: "))
+        .unwrap()
+        .to_string();
+    let session_id = endpoint
+        .strip_prefix("/messages?sessionId=")
+        .unwrap()
+        .to_string();
+
+    let post = |message: Value| {
+        actix_web::test::TestRequest::post()
+            .uri(&endpoint)
+            .insert_header(("content-type", "application/json"))
+            .set_json(message)
+            .to_request()
+    };
+
+    let resp = actix_web::test::call_service(
+        &app,
+        post(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": {"name": "legacy-client", "version": "1.0"}
+            }
+        })),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::ACCEPTED);
+    let initialized = sse_data(&next_chunk(&mut body).await);
+    assert_eq!(initialized["id"], 1);
+    assert_eq!(initialized["result"]["protocolVersion"], "2024-11-05");
+
+    let resp = actix_web::test::call_service(
+        &app,
+        post(json!({"jsonrpc": "2.0", "method": "notifications/initialized"})),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::ACCEPTED);
+
+    actix_web::test::call_service(
+        &app,
+        post(json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"})),
+    )
+    .await;
+    let tools = sse_data(&next_chunk(&mut body).await);
+    assert_eq!(tools["id"], 2);
+    assert_eq!(tools["result"]["tools"][0]["name"], "test_tool");
+
+    // A batch is answered with one event holding every response
+    actix_web::test::call_service(
+        &app,
+        post(json!([
+            {"jsonrpc": "2.0", "id": 3, "method": "ping"},
+            {"jsonrpc": "2.0", "id": 4, "method": "tools/list"}
+        ])),
+    )
+    .await;
+    let batch = sse_data(&next_chunk(&mut body).await);
+    assert_eq!(batch[0]["id"], 3);
+    assert_eq!(batch[1]["result"]["tools"][0]["name"], "test_tool");
+
+    let resp = actix_web::test::call_service(
+        &app,
+        actix_web::test::TestRequest::post()
+            .uri("/messages?sessionId=unknown")
+            .set_json(json!({"jsonrpc": "2.0", "id": 3, "method": "ping"}))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+    // Dropping the stream ends the session
+    drop(body);
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    assert!(server.session(&session_id).is_none());
+}
+
+#[actix_web::test]
+async fn test_client_responses_bound_to_session() {
+    let server = std::sync::Arc::new(HttpMcpServer::builder().legacy_sse(true).build().unwrap());
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+
+    let mut sessions = Vec::new();
+    for _ in 0..2 {
+        let resp = actix_web::test::call_service(
+            &app,
+            actix_web::test::TestRequest::get().uri("/sse").to_request(),
+        )
+        .await;
+        let mut body = Box::pin(resp.into_body());
+        let endpoint_event = next_chunk(&mut body).await;
+        let endpoint = endpoint_event
+            .lines()
+            .find_map(|line| line.strip_prefix("data: "))
+            .unwrap()
+            .to_string();
+        sessions.push((body, endpoint));
+    }
+    let (mut body_a, endpoint_a) = sessions.remove(0);
+    let (mut body_b, endpoint_b) = sessions.remove(0);
+    let session_a = endpoint_a
+        .strip_prefix("/messages?sessionId=")
+        .unwrap()
+        .to_string();
+    let post = |endpoint: &str, message: Value| {
+        actix_web::test::TestRequest::post()
+            .uri(endpoint)
+            .set_json(message)
+            .to_request()
+    };
+
+    let request = {
+        let server = server.clone();
+        actix_web::rt::spawn(
+            async move { server.send_request(&session_a, "roots/list", None).await },
+        )
+    };
+    let sent = sse_data(&next_chunk(&mut body_a).await);
+    assert_eq!(sent["method"], "roots/list");
+
+    // Another session answering with the same ID is ignored
+    let resp = actix_web::test::call_service(
+        &app,
+        post(
+            &endpoint_b,
+            json!({"jsonrpc": "2.0", "id": sent["id"], "result": {"roots": ["forged"]}}),
+        ),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::ACCEPTED);
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    assert!(!request.is_finished());
+
+    // A message with an ID but neither result nor error is an invalid request
+    actix_web::test::call_service(
+        &app,
+        post(&endpoint_b, json!({"jsonrpc": "2.0", "id": sent["id"]})),
+    )
+    .await;
+    let invalid = sse_data(&next_chunk(&mut body_b).await);
+    assert_eq!(invalid["error"]["code"], -32600);
+    assert!(!request.is_finished());
+
+    // The client's error reaches the caller with its code and data
+    actix_web::test::call_service(
+        &app,
+        post(
+            &endpoint_a,
+            json!({
+                "jsonrpc": "2.0",
+                "id": sent["id"],
+                "error": {"code": -32001, "message": "no roots", "data": {"retry": false}}
+            }),
+        ),
+    )
+    .await;
+    match request.await.unwrap() {
+        Err(McpError::ClientError(error)) => {
+            assert_eq!(error.code, -32001);
+            assert_eq!(error.message, "no roots");
+            assert_eq!(error.data, Some(json!({"retry": false})));
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[actix_web::test]
+async fn test_get_stream_delivers_server_messages() {
+    let server = std::sync::Arc::new(HttpMcpServer::builder().build().unwrap());
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+    let session_id = initialize_session!(&app);
+
+    // Nothing is listening before the GET stream opens
+    assert!(!server.notify(&session_id, "notifications/message", json!({})));
+
+    let resp = actix_web::test::call_service(
+        &app,
+        actix_web::test::TestRequest::get()
+            .uri("/mcp")
+            .insert_header(("accept", "text/event-stream"))
+            .insert_header(("mcp-session-id", session_id.as_str()))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+    let mut body = Box::pin(resp.into_body());
+
+    assert!(server.notify(
+        &session_id,
+        "notifications/message",
+        json!({"level": "info", "data": "hello"}),
+    ));
+    let event = sse_data(&next_chunk(&mut body).await);
+    assert_eq!(event["method"], "notifications/message");
+    assert_eq!(event["params"]["data"], "hello");
+
+    // Requests go out on the stream and the client POSTs its response to /mcp
+    let request = {
+        let server = server.clone();
+        let session_id = session_id.clone();
+        actix_web::rt::spawn(
+            async move { server.send_request(&session_id, "roots/list", None).await },
+        )
+    };
+    let sent = sse_data(&next_chunk(&mut body).await);
+    assert_eq!(sent["method"], "roots/list");
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({"jsonrpc": "2.0", "id": sent["id"], "result": {"roots": []}}))
+            .insert_header(("mcp-session-id", session_id.as_str()))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::ACCEPTED);
+    assert_eq!(request.await.unwrap().unwrap(), json!({"roots": []}));
+
+    // Closing the stream unregisters it
+    drop(body);
+    assert!(!server.notify(&session_id, "notifications/message", json!({})));
+}
+
+#[actix_web::test]
+async fn test_websocket_endpoint() {
+    let upgrade = || {
+        actix_web::test::TestRequest::get()
+            .uri("/mcp/ws")
+            .insert_header(("upgrade", "websocket"))
+            .insert_header(("connection", "upgrade"))
+            .insert_header(("sec-websocket-version", "13"))
+            .insert_header(("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ=="))
+            .to_request()
+    };
+
+    let server = HttpMcpServer::builder().websocket(true).build().unwrap();
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, std::sync::Arc::new(server))),
+    )
+    .await;
+    let resp = actix_web::test::call_service(&app, upgrade()).await;
+    assert_eq!(
+        resp.status(),
+        actix_web::http::StatusCode::SWITCHING_PROTOCOLS
+    );
+    assert_eq!(
+        resp.headers().get("sec-websocket-accept").unwrap(),
+        "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+
+    // Plain GET requests are not upgraded
+    let resp = actix_web::test::call_service(
+        &app,
+        actix_web::test::TestRequest::get()
+            .uri("/mcp/ws")
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+    // The endpoint is off by default
+    let server = HttpMcpServer::builder().build().unwrap();
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, std::sync::Arc::new(server))),
+    )
+    .await;
+    let resp = actix_web::test::call_service(&app, upgrade()).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_handle_message_without_http() {
+    use httpmcp_rust::jsonrpc::{JsonRpcRequest, RequestId};
+
+    let server = HttpMcpServer::builder()
+        .tool("test_tool", ToolMeta::new(), test_tool)
+        .build()
+        .unwrap();
+    let ctx = |session_id: Option<&str>| {
+        let mut ctx = RequestContext::new(
+            actix_web::http::header::HeaderMap::new(),
+            "POST".to_string(),
+            "/gateway".to_string(),
+            None,
+        );
+        ctx.session_id = session_id.map(str::to_string);
+        ctx
+    };
+    let request = |id: i64, method: &str, params: Option<Value>| {
+        JsonRpcRequest::new(method, params, Some(RequestId::Number(id)))
+    };
+
+    // Calls without a session are refused until the client initializes
+    let response = server
+        .handle_message(
+            request(
+                1,
+                "tools/call",
+                Some(json!({"name": "test_tool", "arguments": {"message": "hi"}})),
+            ),
+            ctx(None),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.id, Some(RequestId::Number(1)));
+    assert!(response.error.is_some());
+
+    // Sessions are started under the caller's ID
+    let init = json!({
+        "protocolVersion": "2025-03-26",
+        "capabilities": {},
+        "clientInfo": {"name": "gateway", "version": "1.0"}
+    });
+    let response = server
+        .handle_message(request(3, "initialize", Some(init)), ctx(Some("gw-1")))
+        .await
+        .unwrap();
+    assert_eq!(response.result.unwrap()["protocolVersion"], "2025-03-26");
+
+    let response = server
+        .handle_message(request(4, "tools/list", None), ctx(Some("gw-1")))
+        .await
+        .unwrap();
+    assert!(response.error.is_some(), "requests wait for initialized");
+
+    let initialized = JsonRpcRequest::new("notifications/initialized", None, None);
+    assert!(server
+        .handle_message(initialized, ctx(Some("gw-1")))
+        .await
+        .is_none());
+    assert_eq!(
+        server.session("gw-1").unwrap().state,
+        httpmcp_rust::SessionState::Ready
+    );
+
+    let response = server
+        .handle_message(request(5, "tools/list", None), ctx(Some("gw-1")))
+        .await
+        .unwrap();
+    assert_eq!(response.result.unwrap()["tools"][0]["name"], "test_tool");
+
+    let response = server
+        .handle_message(request(2, "no/such/method", None), ctx(Some("gw-1")))
+        .await
+        .unwrap();
+    assert_eq!(response.error.unwrap().code, -32601);
+
+    // Requests are validated like POST bodies
+    let mut invalid = request(7, "tools/list", None);
+    invalid.jsonrpc = "1.0".to_string();
+    let response = server
+        .handle_message(invalid, ctx(Some("gw-1")))
+        .await
+        .unwrap();
+    assert_eq!(response.id, Some(RequestId::Number(7)));
+    assert_eq!(response.error.unwrap().code, -32600);
+    let response = server
+        .handle_message(JsonRpcRequest::new("", None, None), ctx(Some("gw-1")))
+        .await
+        .unwrap();
+    assert_eq!(response.error.unwrap().code, -32600);
+
+    // Unknown sessions are errors, not panics
+    let response = server
+        .handle_message(request(6, "tools/list", None), ctx(Some("missing")))
+        .await
+        .unwrap();
+    assert_eq!(response.id, Some(RequestId::Number(6)));
+    assert!(response.error.is_some());
+}
+
+#[actix_web::test]
+async fn test_mount_servers_under_prefixes() {
+    use httpmcp_rust::transport::configure;
+
+    let public = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .name("public")
+            .sse_responses(false)
+            .endpoint(
+                EndpointMeta::new().route("/health").method("GET"),
+                |_ctx, _body| async { Ok(actix_web::HttpResponse::Ok().body("public")) },
+            )
+            .build()
+            .unwrap(),
+    );
+    let admin = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .name("admin")
+            .sse_responses(false)
+            .legacy_sse(true)
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new()
+            .route(
+                "/",
+                actix_web::web::get().to(|| async { actix_web::HttpResponse::Ok().body("app") }),
+            )
+            .configure(configure(public, "/public"))
+            .configure(configure(admin, "admin/")),
+    )
+    .await;
+
+    let initialize = |uri: &str| {
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "1.0"}
+            }
+        }))
+        .uri(uri)
+        .to_request()
+    };
+    for (prefix, name) in [("/public", "public"), ("/admin", "admin")] {
+        let resp =
+            actix_web::test::call_service(&app, initialize(&format!("{}/mcp", prefix))).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body: Value = actix_web::test::read_body_json(resp).await;
+        assert_eq!(body["result"]["serverInfo"]["name"], name);
+    }
+
+    let get = |uri: &str| actix_web::test::TestRequest::get().uri(uri).to_request();
+    let resp = actix_web::test::call_service(&app, get("/public/health")).await;
+    assert_eq!(actix_web::test::read_body(resp).await, "public");
+    let resp = actix_web::test::call_service(&app, get("/")).await;
+    assert_eq!(actix_web::test::read_body(resp).await, "app");
+    for uri in ["/mcp", "/health", "/admin/health", "/public/sse"] {
+        let resp = actix_web::test::call_service(&app, get(uri)).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::NOT_FOUND,
+            "{}",
+            uri
+        );
+    }
+
+    // The legacy endpoint event points below the prefix
+    let resp = actix_web::test::call_service(&app, get("/admin/sse")).await;
+    let mut body = Box::pin(resp.into_body());
+    assert!(next_chunk(&mut body)
+        .await
+        .contains("data: /admin/messages?sessionId="));
+}
+
+#[tokio::test]
+async fn test_graceful_shutdown() {
+    let closed = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let closed_by_hook = closed.clone();
+    let server = HttpMcpServer::builder()
+        .tool("slow", ToolMeta::new(), |_args, _ctx| async {
+            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+            Ok("done")
+        })
+        .sse_responses(false)
+        .shutdown_timeout(std::time::Duration::from_secs(1))
+        .on_session_closed(move |_session, reason| {
+            closed_by_hook.lock().unwrap().push(reason);
+            async { Ok(()) }
+        })
+        .build()
+        .unwrap();
+    let handle = server.start("127.0.0.1:0").unwrap();
+    let port = handle.addrs()[0].port();
+
+    let url = format!("http://127.0.0.1:{}/mcp", port);
+    let client = reqwest::Client::new();
+    let post = |session_id: Option<&str>, body: Value| {
+        let mut req = client
+            .post(&url)
+            .header("accept", "application/json, text/event-stream")
+            .json(&body);
+        if let Some(id) = session_id {
+            req = req.header("mcp-session-id", id);
+        }
+        req.send()
+    };
+
+    let resp = post(
+        None,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "1.0"}
+            }
+        }),
+    )
+    .await
+    .unwrap();
+    let session_id = resp
+        .headers()
+        .get("mcp-session-id")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    post(
+        Some(&session_id),
+        json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+    )
+    .await
+    .unwrap();
+
+    let mut stream = client
+        .get(&url)
+        .header("accept", "text/event-stream")
+        .header("mcp-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(stream.status(), reqwest::StatusCode::OK);
+
+    let slow_call = post(
+        Some(&session_id),
+        json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "slow", "arguments": {}}
+        }),
+    );
+    let shutdown = async {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        handle.shutdown().await
+    };
+    let (slow, stopped) = tokio::join!(slow_call, shutdown);
+
+    // The in-flight call finished before the server stopped
+    let slow: Value = slow.unwrap().json().await.unwrap();
+    assert_eq!(slow["result"]["content"][0]["text"], "done");
+    stopped.unwrap();
+
+    let mut events = String::new();
+    while let Ok(Some(chunk)) = stream.chunk().await {
+        events.push_str(&String::from_utf8_lossy(&chunk));
+    }
+    assert!(events.contains("event: shutdown\n"));
+    assert_eq!(*closed.lock().unwrap(), vec![SessionCloseReason::Shutdown]);
+    assert!(client.get(&url).send().await.is_err());
+}
+
+#[tokio::test]
+async fn test_shutdown_refuses_new_requests() {
+    use httpmcp_rust::jsonrpc::{JsonRpcRequest, RequestId};
+
+    let server = HttpMcpServer::builder().build().unwrap();
+    server.shutdown(std::time::Duration::ZERO).await;
+
+    let ctx = RequestContext::new(
+        actix_web::http::header::HeaderMap::new(),
+        "POST".to_string(),
+        "/mcp".to_string(),
+        None,
+    );
+    let response = server
+        .handle_message(
+            JsonRpcRequest::new("ping", None, Some(RequestId::Number(1))),
+            ctx,
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.error.unwrap().message, "Server is shutting down");
+
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, std::sync::Arc::new(server))),
+    )
+    .await;
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({"jsonrpc": "2.0", "id": 2, "method": "ping"})).to_request(),
+    )
+    .await;
+    assert_eq!(
+        resp.status(),
+        actix_web::http::StatusCode::SERVICE_UNAVAILABLE
+    );
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn test_mutual_tls() {
+    use httpmcp_rust::TlsConfig;
+
+    let fixture =
+        |name: &str| format!("{}/tests/fixtures/tls/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let server = HttpMcpServer::builder()
+        .require_sessions(false)
+        .tool("whoami", ToolMeta::new(), |_args, ctx| async move {
+            Ok(ctx
+                .peer_certificate
+                .map(|cert| cert.subject)
+                .unwrap_or_default())
+        })
+        .sse_responses(false)
+        .tls(
+            TlsConfig::new(fixture("server.pem"), fixture("server.key"))
+                .client_ca(fixture("ca.pem")),
+        )
+        .build()
+        .unwrap();
+    let handle = server.start("127.0.0.1:0").unwrap();
+    let port = handle.addrs()[0].port();
+
+    let ca = reqwest::Certificate::from_pem(&std::fs::read(fixture("ca.pem")).unwrap()).unwrap();
+    let mut identity = std::fs::read(fixture("client.pem")).unwrap();
+    identity.extend(std::fs::read(fixture("client.key")).unwrap());
+    let client = reqwest::Client::builder()
+        .use_rustls_tls()
+        .add_root_certificate(ca.clone())
+        .identity(reqwest::Identity::from_pem(&identity).unwrap())
+        .build()
+        .unwrap();
+
+    let url = format!("https://localhost:{}/mcp", port);
+    let body: Value = client
+        .post(&url)
+        .header("accept", "application/json, text/event-stream")
+        .json(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "whoami", "arguments": {}}
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        body["result"]["content"][0]["text"],
+        "O=Example, CN=agent-1"
+    );
+
+    // Without a client certificate the handshake is refused
+    let anonymous = reqwest::Client::builder()
+        .use_rustls_tls()
+        .add_root_certificate(ca)
+        .build()
+        .unwrap();
+    assert!(anonymous
+        .post(&url)
+        .header("accept", "application/json, text/event-stream")
+        .json(&json!({"jsonrpc": "2.0", "id": 2, "method": "ping"}))
+        .send()
+        .await
+        .is_err());
+
+    handle.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pre_opened_listener() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = HttpMcpServer::builder()
+        .sse_responses(false)
+        .build()
+        .unwrap();
+    let handle = server.start_listener(listener).unwrap();
+    assert_eq!(handle.addrs(), [addr]);
+
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/mcp", addr))
+        .header("accept", "application/json, text/event-stream")
+        .json(&json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    // Loopback defaults apply to listeners bound on loopback
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/mcp", addr))
+        .header("accept", "application/json, text/event-stream")
+        .header("host", "evil.example.com")
+        .json(&json!({"jsonrpc": "2.0", "id": 2, "method": "ping"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    handle.shutdown().await.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_unix_domain_socket() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let path = std::env::temp_dir().join(format!("httpmcp-{}.sock", uuid::Uuid::new_v4()));
+    let server = || {
+        HttpMcpServer::builder()
+            .require_sessions(false)
+            .tool("peer", ToolMeta::new(), |_args, ctx| async move {
+                Ok(format!("{:?}", ctx.remote_addr))
+            })
+            .sse_responses(false)
+            .build()
+            .unwrap()
+    };
+
+    // A regular file at the path is never deleted
+    std::fs::write(&path, "not a socket").unwrap();
+    let err = server().start_uds(&path).map(drop).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+    #[cfg(feature = "tls")]
+    {
+        let fixture =
+            |name: &str| format!("{}/tests/fixtures/tls/{}", env!("CARGO_MANIFEST_DIR"), name);
+        let err = HttpMcpServer::builder()
+            .tls(httpmcp_rust::TlsConfig::new(
+                fixture("server.pem"),
+                fixture("server.key"),
+            ))
+            .build()
+            .unwrap()
+            .start_uds(&path)
+            .map(drop)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "not a socket");
+    std::fs::remove_file(&path).unwrap();
+
+    // A socket left behind by an earlier run is replaced
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    let handle = server().start_uds(&path).unwrap();
+    assert!(handle.addrs().is_empty());
+
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": {"name": "peer", "arguments": {}}
+    })
+    .to_string();
+    let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+    let request = format!(
+        "POST /mcp HTTP/1.1\r\nHost: localhost\r\nAccept: application/json, text/event-stream\r\n\
+         Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"));
+    assert!(response.contains(r#""text":"None""#));
+
+    // A second server cannot take over a socket that is still being served
+    let err = server().start_uds(&path).map(drop).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+    let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"));
+
+    handle.shutdown().await.unwrap();
+    assert!(!path.exists());
+}
+
+#[actix_web::test]
+async fn test_payload_limits_per_endpoint() {
+    use futures::StreamExt;
+    use httpmcp_rust::ServerConfig;
+
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .server_config(
+                ServerConfig::new()
+                    .mcp_payload_limit(4096)
+                    .endpoint_payload_limit(64)
+                    .multipart_payload_limit(512),
+            )
+            .sse_responses(false)
+            .endpoint(
+                EndpointMeta::new().route("/api/echo").method("POST"),
+                |_ctx, body| async move { Ok(actix_web::HttpResponse::Ok().json(body)) },
+            )
+            .multipart_endpoint(
+                EndpointMeta::new().route("/api/upload").method("POST"),
+                |_ctx, mut multipart| async move {
+                    let mut size = 0;
+                    while let Some(field) = multipart.next().await {
+                        let mut field =
+                            field.map_err(|e| McpError::InternalError(e.to_string()))?;
+                        while let Some(chunk) = field.next().await {
+                            size += chunk
+                                .map_err(|e| McpError::InternalError(e.to_string()))?
+                                .len();
+                        }
+                    }
+                    Ok(actix_web::HttpResponse::Ok().json(json!({"size": size})))
+                },
+            )
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+
+    let echo = |body: Value| {
+        actix_web::test::TestRequest::post()
+            .uri("/api/echo")
+            .set_json(body)
+            .to_request()
+    };
+    let body: Value =
+        actix_web::test::call_and_read_body_json(&app, echo(json!({"ok": true}))).await;
+    assert_eq!(body, json!({"ok": true}));
+    let resp = actix_web::test::call_service(&app, echo(json!({"x": "y".repeat(100)}))).await;
+    assert_eq!(
+        resp.status(),
+        actix_web::http::StatusCode::PAYLOAD_TOO_LARGE
+    );
+
+    // The MCP endpoint has its own, larger limit
+    let resp = actix_web::test::call_service(
+        &app,
+        mcp_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "ping",
+            "params": {"padding": "x".repeat(1024)}
+        }))
+        .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+    let upload = |size: usize| {
+        let body = format!(
+            "--XYZ\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.csv\"\r\n\
+             Content-Type: text/csv\r\n\r\n{}\r\n--XYZ--\r\n",
+            "a".repeat(size)
+        );
+        actix_web::test::TestRequest::post()
+            .uri("/api/upload")
+            .insert_header(("content-type", "multipart/form-data; boundary=XYZ"))
+            .set_payload(body)
+            .to_request()
+    };
+    let body: Value = actix_web::test::call_and_read_body_json(&app, upload(100)).await;
+    assert_eq!(body["size"], 100);
+    let resp = actix_web::test::call_service(&app, upload(1024)).await;
+    assert_eq!(
+        resp.status(),
+        actix_web::http::StatusCode::PAYLOAD_TOO_LARGE
+    );
+}
+
+#[tokio::test]
+async fn test_server_tuning() {
+    use httpmcp_rust::ServerConfig;
+
+    let server = HttpMcpServer::builder()
+        .server_config(
+            ServerConfig::new()
+                .workers(1)
+                .keep_alive(std::time::Duration::from_secs(1))
+                .client_request_timeout(std::time::Duration::from_millis(500))
+                .client_disconnect_timeout(std::time::Duration::from_millis(500))
+                .max_connections(16)
+                .backlog(8),
+        )
+        .sse_responses(false)
+        .build()
+        .unwrap();
+    let handle = server.start("127.0.0.1:0").unwrap();
+    let addr = handle.addrs()[0];
+
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/mcp", addr))
+        .header("accept", "application/json, text/event-stream")
+        .json(&json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    // A client that never sends its request head is timed out with 408
+    let mut idle = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let mut response = String::new();
+    tokio::io::AsyncReadExt::read_to_string(&mut idle, &mut response)
+        .await
+        .unwrap();
+    assert!(response.starts_with("HTTP/1.1 408"));
+
+    handle.shutdown().await.unwrap();
+}
+
+async fn large_tool(_args: HashMap<String, Value>, _ctx: RequestContext) -> Result<Value> {
+    Ok(json!({"text": "compressible ".repeat(512)}))
+}
+
+fn gunzip(data: &[u8]) -> String {
+    use std::io::Write;
+    let mut decoder = flate2::write::GzDecoder::new(Vec::new());
+    decoder.write_all(data).unwrap();
+    decoder.flush().unwrap();
+    String::from_utf8(decoder.get_ref().clone()).unwrap()
+}
+
+#[actix_web::test]
+async fn test_response_compression() {
+    let server = std::sync::Arc::new(
+        HttpMcpServer::builder()
+            .require_sessions(false)
+            .tool("large_tool", ToolMeta::new(), large_tool)
+            .sse_responses(false)
+            .legacy_sse(true)
+            .compression(true)
+            .build()
+            .unwrap(),
+    );
+    let app = actix_web::test::init_service(
+        actix_web::App::new().configure(|cfg| create_app(cfg, server.clone())),
+    )
+    .await;
+
+    let call = |id: u64, method: &str, encoding: &str| {
+        actix_web::test::TestRequest::post()
+            .uri("/mcp")
+            .insert_header(("accept", "application/json, text/event-stream"))
+            .insert_header(("accept-encoding", encoding.to_string()))
+            .set_json(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": method,
+                "params": {"name": "large_tool", "arguments": {}}
+            }))
+            .to_request()
+    };
+
+    let resp = actix_web::test::call_service(&app, call(1, "tools/call", "br;q=0.5, gzip")).await;
+    assert_eq!(resp.headers().get("content-encoding").unwrap(), "gzip");
+    assert_eq!(resp.headers().get("vary").unwrap(), "accept-encoding");
+    let body = actix_web::test::read_body(resp).await;
+    let response: Value = serde_json::from_str(&gunzip(&body)).unwrap();
+    assert_eq!(response["id"], 1);
+    assert!(response["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("compressible compressible"));
+
+    // Small bodies and clients without Accept-Encoding are left alone
+    let resp = actix_web::test::call_service(&app, call(2, "ping", "gzip")).await;
+    assert!(resp.headers().get("content-encoding").is_none());
+    let resp = actix_web::test::call_service(&app, call(3, "tools/call", "identity")).await;
+    assert!(resp.headers().get("content-encoding").is_none());
+
+    // Each SSE event decodes on its own without waiting for the stream to end
+    let resp = actix_web::test::call_service(
+        &app,
+        actix_web::test::TestRequest::get()
+            .uri("/sse")
+            .insert_header(("accept-encoding", "gzip"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.headers().get("content-encoding").unwrap(), "gzip");
+    let mut body = Box::pin(resp.into_body());
+    let chunk =
+        std::future::poll_fn(|cx| actix_web::body::MessageBody::poll_next(body.as_mut(), cx))
+            .await
+            .unwrap()
+            .unwrap();
+    assert!(gunzip(&chunk).contains("event: endpoint\n"));
+}